@@ -0,0 +1,182 @@
+//! Find many patterns at once with the [Aho–Corasick algorithm](https://en.wikipedia.org/wiki/Aho%E2%80%93Corasick_algorithm).
+
+use std::collections::VecDeque;
+
+/// An automaton that finds all occurrences of a set of patterns in a single pass.
+/// Every pattern has a value that is returned with its matches.
+///
+/// ```rust
+/// # use aoc2023::aho_corasick::AhoCorasick;
+/// let matcher = AhoCorasick::new([("one", 1), ("two", 2), ("eight", 8)]);
+/// let found: Vec<_> = matcher.find_overlapping("eightwone").map(|m| *m.value).collect();
+/// assert_eq!(found, [8, 2, 1]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AhoCorasick<T> {
+    forward: Automaton,
+    /// Built from the reversed patterns, to search from the end.
+    backward: Automaton,
+    patterns: Vec<(usize, T)>,
+}
+
+/// Where a pattern was found.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Match<'a, T> {
+    /// Byte offset of the first byte.
+    pub start: usize,
+    /// Byte offset after the last byte.
+    pub end: usize,
+    pub value: &'a T,
+}
+
+impl<T> AhoCorasick<T> {
+    pub fn new<P: AsRef<[u8]>>(patterns: impl IntoIterator<Item = (P, T)>) -> Self {
+        let (bytes, values): (Vec<_>, Vec<_>) = patterns
+            .into_iter()
+            .map(|(pattern, value)| (pattern.as_ref().to_vec(), value))
+            .unzip();
+        let reversed: Vec<Vec<u8>> = bytes
+            .iter()
+            .map(|b| b.iter().rev().copied().collect())
+            .collect();
+
+        Self {
+            forward: Automaton::new(&bytes),
+            backward: Automaton::new(&reversed),
+            patterns: bytes.iter().map(|b| b.len()).zip(values).collect(),
+        }
+    }
+
+    /// All matches, including overlapping ones, ordered by their end.
+    pub fn find_overlapping<'a>(
+        &'a self,
+        haystack: &'a str,
+    ) -> impl Iterator<Item = Match<'a, T>> + 'a {
+        let mut state = 0;
+        haystack.bytes().enumerate().flat_map(move |(i, byte)| {
+            state = self.forward.step(state, byte);
+            self.forward.outputs[state]
+                .iter()
+                .map(move |&p| self.found(p, i + 1 - self.patterns[p].0))
+        })
+    }
+
+    /// The match that starts first. Stops reading the haystack as soon as no earlier match is possible.
+    ///
+    /// ```rust
+    /// # use aoc2023::aho_corasick::AhoCorasick;
+    /// let matcher = AhoCorasick::new([("bc", 1), ("abcd", 2)]);
+    /// assert_eq!(*matcher.first("xabcd").unwrap().value, 2);
+    /// assert_eq!(matcher.first("xyz"), None);
+    /// ```
+    pub fn first<'a>(&'a self, haystack: &'a str) -> Option<Match<'a, T>> {
+        let longest = self.patterns.iter().map(|&(len, _)| len).max().unwrap_or(0);
+        let mut best: Option<Match<T>> = None;
+
+        for m in self.find_overlapping(haystack) {
+            // Matches that end later than this can only start before best if best is short.
+            if best
+                .as_ref()
+                .is_some_and(|best| m.end > best.start + longest)
+            {
+                break;
+            }
+            if best.as_ref().is_none_or(|best| m.start < best.start) {
+                best = Some(m);
+            }
+        }
+
+        best
+    }
+
+    /// The match that starts last. Searches from the end and stops at the first match.
+    ///
+    /// ```rust
+    /// # use aoc2023::aho_corasick::AhoCorasick;
+    /// let matcher = AhoCorasick::new([("one", 1), ("eight", 8)]);
+    /// let m = matcher.last("oneightx").unwrap();
+    /// assert_eq!((m.start, *m.value), (2, 8));
+    /// ```
+    pub fn last<'a>(&'a self, haystack: &'a str) -> Option<Match<'a, T>> {
+        let mut state = 0;
+        for (i, byte) in haystack.bytes().rev().enumerate() {
+            state = self.backward.step(state, byte);
+            // The earliest match in the reversed haystack starts last in the original.
+            if let Some(&p) = self.backward.outputs[state].first() {
+                let start = haystack.len() - (i + 1);
+                return Some(self.found(p, start));
+            }
+        }
+        None
+    }
+
+    fn found(&self, pattern: usize, start: usize) -> Match<'_, T> {
+        let (len, value) = &self.patterns[pattern];
+        Match {
+            start,
+            end: start + len,
+            value,
+        }
+    }
+}
+
+/// A trie with failure links resolved into a complete transition table.
+#[derive(Clone, Debug)]
+struct Automaton {
+    transitions: Vec<[usize; 256]>,
+    /// Patterns that end in every state, longest first.
+    outputs: Vec<Vec<usize>>,
+}
+
+impl Automaton {
+    fn new(patterns: &[Vec<u8>]) -> Self {
+        let mut automaton = Self {
+            transitions: vec![[0; 256]],
+            outputs: vec![Vec::new()],
+        };
+
+        // Build the trie, 0 means there is no edge yet, because nothing can go back to the root.
+        for (p, pattern) in patterns.iter().enumerate() {
+            let mut state = 0;
+            for &byte in pattern {
+                if automaton.transitions[state][byte as usize] == 0 {
+                    automaton.transitions.push([0; 256]);
+                    automaton.outputs.push(Vec::new());
+                    automaton.transitions[state][byte as usize] = automaton.transitions.len() - 1;
+                }
+                state = automaton.transitions[state][byte as usize];
+            }
+            automaton.outputs[state].push(p);
+        }
+
+        // Breadth first, so the failure state of every state is finished before it is needed.
+        let mut failure = vec![0; automaton.transitions.len()];
+        let mut queue: VecDeque<usize> = automaton.transitions[0]
+            .iter()
+            .copied()
+            .filter(|&next| next != 0)
+            .collect();
+
+        while let Some(state) = queue.pop_front() {
+            let fail = failure[state];
+            let inherited = automaton.outputs[fail].clone();
+            automaton.outputs[state].extend(inherited);
+
+            for byte in 0..256 {
+                let next = automaton.transitions[state][byte];
+                if next == 0 {
+                    automaton.transitions[state][byte] = automaton.transitions[fail][byte];
+                } else {
+                    failure[next] = automaton.transitions[fail][byte];
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        automaton
+    }
+
+    fn step(&self, state: usize, byte: u8) -> usize {
+        self.transitions[state][byte as usize]
+    }
+}
@@ -0,0 +1,142 @@
+//! Count allocations, to find the days that allocate a lot.
+//!
+//! With the `count-allocations` feature [`Counting`] is the global allocator of every program in this crate,
+//! and [`measure`](crate::measure::measure) reports the allocations of every phase.
+//!
+//! ```shell
+//! cargo build --release --features count-allocations
+//! ./target/release/aoc run --all
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+static COUNT: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static LIVE: AtomicU64 = AtomicU64::new(0);
+static PEAK: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting every allocation and the bytes that are live.
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(0, layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size() as u64, Relaxed);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(0, layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            allocated(layout.size(), new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Count an allocation that replaces `old` bytes with `new` bytes.
+fn allocated(old: usize, new: usize) {
+    COUNT.fetch_add(1, Relaxed);
+    BYTES.fetch_add(new as u64, Relaxed);
+    let live = if new >= old {
+        LIVE.fetch_add((new - old) as u64, Relaxed) + (new - old) as u64
+    } else {
+        LIVE.fetch_sub((old - new) as u64, Relaxed) - (old - new) as u64
+    };
+    PEAK.fetch_max(live, Relaxed);
+}
+
+/// Whether allocations are counted, which needs the `count-allocations` feature.
+pub fn enabled() -> bool {
+    cfg!(feature = "count-allocations")
+}
+
+/// The allocations made while running something.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Allocations {
+    /// The number of allocations, growing an allocation counts as one.
+    pub count: u64,
+    /// All bytes that were allocated, including those that were freed again.
+    pub bytes: u64,
+    /// The most bytes that were live at once, on top of those that were live before.
+    pub peak: u64,
+}
+
+impl Display for Allocations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} allocations, {}, peak {}",
+            self.count,
+            human_bytes(self.bytes),
+            human_bytes(self.peak)
+        )
+    }
+}
+
+/// Count the allocations made while running `f`. Everything is zero unless allocations are [`enabled`].
+/// Allocations of other threads at the same time are counted as well.
+///
+/// ```rust
+/// # use aoc2023::allocations::{count, enabled};
+/// let (numbers, allocations) = count(|| vec![0u8; 1000]);
+/// assert_eq!(numbers.len(), 1000);
+/// if enabled() {
+///     assert!(allocations.count >= 1 && allocations.bytes >= 1000 && allocations.peak >= 1000);
+/// }
+/// ```
+pub fn count<T>(f: impl FnOnce() -> T) -> (T, Allocations) {
+    let (count, bytes, live) = (COUNT.load(Relaxed), BYTES.load(Relaxed), LIVE.load(Relaxed));
+    PEAK.store(live, Relaxed);
+    let result = f();
+    let allocations = Allocations {
+        count: COUNT.load(Relaxed) - count,
+        bytes: BYTES.load(Relaxed) - bytes,
+        peak: PEAK.load(Relaxed).saturating_sub(live),
+    };
+    (result, allocations)
+}
+
+/// Bytes with a binary unit, like `1.5 KiB`.
+///
+/// ```rust
+/// # use aoc2023::allocations::human_bytes;
+/// assert_eq!(human_bytes(512), "512 B");
+/// assert_eq!(human_bytes(1536), "1.5 KiB");
+/// assert_eq!(human_bytes(3 << 30), "3.0 GiB");
+/// ```
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
@@ -0,0 +1,68 @@
+//! Properties of the real inputs that solutions rely on without checking them while solving.
+//!
+//! Days declare their assumptions as predicates over the input, `aoc check <day>` evaluates them.
+//! This tells "the code is wrong" apart from "this input breaks the shortcut".
+//! Every day answers `--check`, days without assumptions pass [`none`].
+
+use crate::{flag, input};
+
+/// A property of the input and whether it holds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Assumption {
+    pub description: &'static str,
+    pub holds: bool,
+}
+
+impl Assumption {
+    pub fn new(description: &'static str, holds: bool) -> Self {
+        Self { description, holds }
+    }
+}
+
+/// The assumptions of a day that takes no shortcuts.
+pub fn none(_input: &str) -> anyhow::Result<Vec<Assumption>> {
+    Ok(Vec::new())
+}
+
+/// One line per assumption, marked `ok` or `BROKEN`.
+///
+/// ```rust
+/// # use aoc2023::assumptions::{report, Assumption};
+/// let assumptions = [Assumption::new("lines are short", true), Assumption::new("no tabs", false)];
+/// assert_eq!(report(&assumptions), "ok      lines are short\nBROKEN  no tabs\n");
+/// assert_eq!(report(&[]), "no assumptions checked\n");
+/// ```
+pub fn report(assumptions: &[Assumption]) -> String {
+    if assumptions.is_empty() {
+        return "no assumptions checked\n".to_string();
+    }
+    assumptions
+        .iter()
+        .map(|assumption| {
+            let status = if assumption.holds { "ok" } else { "BROKEN" };
+            format!("{status:<8}{}\n", assumption.description)
+        })
+        .collect()
+}
+
+/// If the program was started with `--check`, print which assumptions hold for the input and exit.
+/// The exit code is 1 if any of them is broken and 2 if the input could not be read far enough to check them.
+pub fn check_if_requested(
+    default_input: &'static str,
+    assumptions: fn(&str) -> anyhow::Result<Vec<Assumption>>,
+) {
+    if !flag("--check") {
+        return;
+    }
+
+    let assumptions = match assumptions(&input(default_input)) {
+        Ok(assumptions) => assumptions,
+        Err(error) => {
+            eprintln!("cannot check the assumptions: {error:#}");
+            std::process::exit(2);
+        }
+    };
+    print!("{}", report(&assumptions));
+    let all_hold = assumptions.iter().all(|assumption| assumption.holds);
+    std::process::exit(if all_hold { 0 } else { 1 });
+}
@@ -1,83 +1,86 @@
+use anyhow::Context;
+use aoc2023::aho_corasick::AhoCorasick;
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::input;
+use aoc2023::measure::measure;
 use itertools::assert_equal;
 
 const INPUT: &str = include_str!("../../input/01");
 const TEST_INPUT: &str = include_str!("../../input/01-test");
 const TEST_INPUT2: &str = include_str!("../../input/01-test-2");
 
+const ENGLISH: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const GERMAN: [&str; 9] = [
+    "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+];
+
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[TEST_INPUT, TEST_INPUT2], |input| {
+        calibration_values(&digit_matcher(&ENGLISH), input)
+    });
+
     assert_eq!(part1(TEST_INPUT), 142);
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || {
+        calibration_values(&digit_matcher(&ENGLISH), &input).unwrap()
+    });
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
 
-    assert_equal(replace_with_digits("eightwothree"), [8, 2, 3]);
-    assert_equal(replace_with_digits("zoneight234"), [1, 8, 2, 3, 4]);
+    let english = digit_matcher(&ENGLISH);
+    assert_equal(parse_digits(&english, "eightwothree"), [8, 2, 3]);
+    assert_equal(parse_digits(&english, "zoneight234"), [1, 8, 2, 3, 4]);
+    let german = digit_matcher(&GERMAN);
+    assert_eq!(calibration_value(&german, "xfünfzehnachtzig"), Some(58));
+    assert_eq!(calibration_value(&english, "a0b"), Some(0));
 
     assert_eq!(part2(TEST_INPUT2), 281);
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> u32 {
-    input
-        .lines()
-        .map(parse_digits)
-        .map(calibration_value_from_line)
-        .sum()
+    let matcher = digit_matcher(&[]);
+    calibration_values(&matcher, input).unwrap().iter().sum()
 }
 
 fn part2(input: &str) -> u32 {
-    input
-        .lines()
-        .map(replace_with_digits)
-        .map(calibration_value_from_line)
-        .sum()
+    let matcher = digit_matcher(&ENGLISH);
+    calibration_values(&matcher, input).unwrap().iter().sum()
 }
 
-fn calibration_value_from_line(line: Vec<u32>) -> u32 {
-    let first = line.first().copied().unwrap();
-    let last = line.last().copied().unwrap_or(first);
-    first * 10 + last
+/// Match the digits 0 to 9 and the given words for 1 to 9.
+fn digit_matcher(words: &[&str]) -> AhoCorasick<u32> {
+    let digits = (0..=9).map(|d| (d.to_string(), d));
+    let words = words.iter().zip(1..).map(|(word, d)| (word.to_string(), d));
+    AhoCorasick::new(digits.chain(words))
 }
 
-/// Parse all digits from the input
-fn parse_digits(line: &str) -> Vec<u32> {
-    line.chars().flat_map(|c| c.to_digit(10)).collect()
+/// The calibration value of every line, fails if a line has no digits.
+fn calibration_values(matcher: &AhoCorasick<u32>, input: &str) -> anyhow::Result<Vec<u32>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            calibration_value(matcher, line)
+                .with_context(|| format!("line {} has no digits", i + 1))
+        })
+        .collect()
 }
 
-/// Replace all words with digits, keep digits and discard everything else.
-fn replace_with_digits(line: &str) -> Vec<u32> {
-    let replacements = [
-        ("one", 1),
-        ("two", 2),
-        ("three", 3),
-        ("four", 4),
-        ("five", 5),
-        ("six", 6),
-        ("seven", 7),
-        ("eight", 8),
-        ("nine", 9),
-    ];
-
-    let mut result = Vec::new();
-    let mut buf = line.to_string();
-
-    while !buf.is_empty() {
-        if let Some(c) = buf.chars().next() {
-            if let Some(num) = c.to_digit(10) {
-                result.push(num);
-                buf.remove(0);
-                continue;
-            }
-        }
-
-        for (find, replace) in replacements {
-            if buf.starts_with(find) {
-                result.push(replace);
-                buf.remove(0);
-                continue;
-            }
-        }
-
-        buf.remove(0);
-    }
+/// Combine the first and the last digit of the line, searching only as far as needed from both ends.
+fn calibration_value(matcher: &AhoCorasick<u32>, line: &str) -> Option<u32> {
+    let first = matcher.first(line)?.value;
+    let last = matcher.last(line)?.value;
+    Some(first * 10 + last)
+}
 
-    result
+/// Parse all digits from the line, words may overlap.
+fn parse_digits<'a>(
+    matcher: &'a AhoCorasick<u32>,
+    line: &'a str,
+) -> impl Iterator<Item = u32> + 'a {
+    matcher.find_overlapping(line).map(|m| *m.value)
 }
@@ -1,16 +1,29 @@
+use anyhow::{bail, Context};
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::input;
+use aoc2023::measure::measure;
+
 const INPUT: &str = include_str!("../../input/02");
 const TEST_INPUT: &str = include_str!("../../input/02-test");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[TEST_INPUT], parse);
+
     assert_eq!(part1(TEST_INPUT), 8);
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
 
     assert_eq!(part2(TEST_INPUT), 2286);
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> u32 {
     parse(input)
+        .unwrap()
+        .into_iter()
         .filter(|game| game.is_possible())
         .map(|game| game.id)
         .sum()
@@ -18,6 +31,8 @@ fn part1(input: &str) -> u32 {
 
 fn part2(input: &str) -> u32 {
     parse(input)
+        .unwrap()
+        .iter()
         .map(|game| game.max_set())
         .map(|set| set.power())
         .sum()
@@ -43,17 +58,18 @@ impl Game {
         })
     }
 
-    fn parse(line: &str) -> Self {
-        let (game, revealed) = line.split_once(": ").unwrap();
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let (game, revealed) = line.split_once(": ").context("missing ': '")?;
         let id = game
-            .split_ascii_whitespace()
-            .last()
-            .unwrap()
-            .parse()
-            .unwrap();
-        let revealed = revealed.split("; ").map(Set::parse).collect();
-
-        Self { id, revealed }
+            .strip_prefix("Game ")
+            .context("missing game id")?
+            .parse()?;
+        let revealed = revealed
+            .split("; ")
+            .map(Set::parse)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { id, revealed })
     }
 }
 
@@ -69,25 +85,31 @@ impl Set {
         self.r * self.g * self.b
     }
 
-    fn parse(s: &str) -> Self {
+    fn parse(s: &str) -> anyhow::Result<Self> {
         let colors = s.split(", ");
         let mut result = Self::default();
 
         for color in colors {
-            let (count, color) = color.split_once(' ').unwrap();
-            let count = count.parse().unwrap();
+            let (count, color) = color
+                .split_once(' ')
+                .with_context(|| format!("{color:?} is not a count and a color"))?;
+            let count = count.parse()?;
             match color {
                 "red" => result.r = count,
                 "green" => result.g = count,
                 "blue" => result.b = count,
-                other => panic!("unknown color: {other}"),
+                other => bail!("unknown color: {other}"),
             }
         }
 
-        result
+        Ok(result)
     }
 }
 
-fn parse(input: &str) -> impl Iterator<Item = Game> + '_ {
-    input.lines().map(Game::parse)
+fn parse(input: &str) -> anyhow::Result<Vec<Game>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Game::parse(line).with_context(|| format!("line {}", i + 1)))
+        .collect()
 }
@@ -1,109 +1,234 @@
+use anyhow::Context;
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::render::{Picture, Rgb};
+use aoc2023::{flag, input, option, Vec2};
+use itertools::Itertools;
+use serde::Serialize;
 use std::collections::HashMap;
 
 const INPUT: &str = include_str!("../../input/03");
 const TEST_INPUT: &str = include_str!("../../input/03-test");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[TEST_INPUT], Schematic::parse);
+    if let Some(path) = option("--export") {
+        Schematic::parse(&input(INPUT))
+            .unwrap()
+            .picture()
+            .save(path)
+            .unwrap();
+        return;
+    }
+    if flag("--json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Schematic::parse(&input(INPUT)).unwrap()).unwrap()
+        );
+        return;
+    }
+
     assert_eq!(part1(TEST_INPUT), 4361);
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || Schematic::parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_eq!(part2(TEST_INPUT), 467835);
-    println!("Part 2: {}", part2(INPUT));
+    let schematic = Schematic::parse("2.3\n.*.\n4#5").unwrap();
+    assert_eq!(
+        schematic
+            .gear_ratios('*', 4)
+            .map(Result::unwrap)
+            .sum::<u64>(),
+        120
+    );
+    let huge = Schematic::parse("99999999999*99999999999").unwrap();
+    assert!(matches!(huge.gear_ratios('*', 2).next(), Some(Err(_))));
+    assert_eq!(schematic.numbers[2].symbols.len(), 2);
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> u64 {
-    parse_part_numbers(input)
-        .into_iter()
-        .map(|part| part.value)
+    Schematic::parse(input)
+        .unwrap()
+        .part_numbers()
+        .map(|number| number.value)
         .sum()
 }
 
 fn part2(input: &str) -> u64 {
-    let parts_with_gear = parse_part_numbers(input)
-        .into_iter()
-        .filter(|part| part.symbol == '*');
-
-    let mut gears: HashMap<(usize, usize), Vec<PartNumber>> = HashMap::new();
-    for part in parts_with_gear {
-        gears.entry(part.symbol_index).or_default().push(part);
-    }
+    Schematic::parse(input)
+        .unwrap()
+        .gear_ratios('*', 2)
+        .try_fold(0u64, |sum, ratio| {
+            sum.checked_add(ratio?)
+                .context("sum of gear ratios does not fit into u64")
+        })
+        .unwrap()
+}
 
-    gears.values().flat_map(gear_ratio).sum()
+/// All numbers and symbols of the engine schematic and which of them are adjacent.
+#[derive(Debug, Serialize)]
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
 }
 
-/// Return the gear ratio for the parts if there are exactly two parts.
-/// Returns None otherwise.
-fn gear_ratio(parts: impl AsRef<[PartNumber]>) -> Option<u64> {
-    let parts = parts.as_ref();
-    if parts.len() == 2 {
-        Some(parts.iter().map(|part| part.value).product())
-    } else {
-        None
+impl Schematic {
+    /// Numbers that are adjacent to at least one symbol.
+    fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .filter(|number| !number.symbols.is_empty())
     }
-}
 
-/// A part number is a number beside a symbol.
-struct PartNumber {
-    value: u64,
-    /// (col, row) where the symbol for this part is.
-    symbol_index: (usize, usize),
-    symbol: char,
-}
+    /// Products of the adjacent numbers of every symbol `c` that has exactly `k` adjacent numbers.
+    /// Fails for a gear whose product does not fit into u64.
+    fn gear_ratios(&self, c: char, k: usize) -> impl Iterator<Item = anyhow::Result<u64>> + '_ {
+        self.gears(c, k).map(|gear| {
+            gear.numbers
+                .iter()
+                .try_fold(1u64, |product, &n| {
+                    product.checked_mul(self.numbers[n].value)
+                })
+                .with_context(|| {
+                    let (x, y) = gear.position;
+                    format!("gear ratio at column {x}, row {y} does not fit into u64")
+                })
+        })
+    }
 
-fn parse_part_numbers(input: &str) -> Vec<PartNumber> {
-    let lines: Vec<&str> = input.lines().collect();
+    /// Symbols `c` with exactly `k` adjacent numbers.
+    fn gears(&self, c: char, k: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .filter(move |symbol| symbol.symbol == c && symbol.numbers.len() == k)
+    }
 
-    let mut number = String::new();
-    let mut numbers: Vec<PartNumber> = Vec::new();
+    /// Part numbers in green, other numbers in gray, symbols in yellow and gears in red.
+    fn picture(&self) -> Picture {
+        let gears = self.gears('*', 2).map(|gear| gear.position).collect_vec();
+        let numbers = self.numbers.iter().flat_map(|number| {
+            let color = if number.symbols.is_empty() {
+                Rgb::GRAY
+            } else {
+                Rgb::GREEN
+            };
+            let (x, y) = number.position;
+            (x..x + number.len).map(move |x| ((x, y), color))
+        });
+        let symbols = self.symbols.iter().map(|symbol| {
+            let color = if gears.contains(&symbol.position) {
+                Rgb::RED
+            } else {
+                Rgb::YELLOW
+            };
+            (symbol.position, color)
+        });
 
-    for (line_index, line) in lines.iter().enumerate() {
-        for (c_index, c) in line.chars().enumerate() {
-            if let CharType::Number(c) = c.into() {
-                number.push(c);
-            }
+        let cells = numbers
+            .chain(symbols)
+            .map(|((x, y), color)| (Vec2::new(x as i64, y as i64), color));
+        Picture::new(cells, |&color| color)
+    }
 
-            let line_end = c_index == line.len() - 1;
-            let number_end = match c.into() {
-                CharType::Number(_) => false,
-                CharType::Empty | CharType::Symbol(_) => true,
-            };
-            let has_number = !number.is_empty();
-            let stop_number_parsing = has_number && (line_end || number_end);
-            if !stop_number_parsing {
-                continue;
+    /// Fails if a number does not fit into u64.
+    fn parse(input: &str) -> anyhow::Result<Self> {
+        let grid = input
+            .lines()
+            .map(|line| line.chars().collect_vec())
+            .collect_vec();
+
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        for (y, row) in grid.iter().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                match row[x].into() {
+                    CharType::Empty => x += 1,
+                    CharType::Symbol(symbol) => {
+                        symbols.push(Symbol::new(symbol, (x, y)));
+                        x += 1;
+                    }
+                    CharType::Digit => {
+                        let len = row[x..].iter().take_while(|c| c.is_ascii_digit()).count();
+                        let digits = row[x..x + len].iter().collect::<String>();
+                        let value = digits
+                            .parse()
+                            .with_context(|| format!("{digits} at ({x}, {y}) is too big"))?;
+                        numbers.push(Number::new(value, (x, y), len));
+                        x += len;
+                    }
+                }
             }
+        }
 
-            // Find any symbol around number.
-            let x_range = (c_index - number.len()).saturating_sub(1)..=c_index;
-            let y_range = line_index.saturating_sub(1)..=(line_index + 1);
-            'find_symbol: for x in x_range {
-                for y in y_range.clone() {
-                    let c = lines.get(y).and_then(|line| line.chars().nth(x));
-                    let Some(c) = c else {
-                        continue;
-                    };
-
-                    if let CharType::Symbol(c) = c.into() {
-                        // The number is a part number.
-                        numbers.push(PartNumber {
-                            value: number.parse().unwrap(),
-                            symbol_index: (x, y),
-                            symbol: c,
-                        });
-                        break 'find_symbol;
-                    }
+        // Connect every number with every symbol around it.
+        let symbol_index: HashMap<(usize, usize), usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| (symbol.position, i))
+            .collect();
+        for (n, number) in numbers.iter_mut().enumerate() {
+            let (x, y) = number.position;
+            let around = (x.saturating_sub(1)..=x + number.len)
+                .cartesian_product(y.saturating_sub(1)..=y + 1);
+            for position in around {
+                if let Some(&s) = symbol_index.get(&position) {
+                    number.symbols.push(s);
+                    symbols[s].numbers.push(n);
                 }
             }
+        }
 
-            // Clear before next number is parsed.
-            number.clear();
+        Ok(Self { numbers, symbols })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Number {
+    value: u64,
+    /// (col, row) of the first digit.
+    position: (usize, usize),
+    /// Number of digits.
+    len: usize,
+    /// Indices of all adjacent symbols.
+    symbols: Vec<usize>,
+}
+
+impl Number {
+    fn new(value: u64, position: (usize, usize), len: usize) -> Self {
+        Self {
+            value,
+            position,
+            len,
+            symbols: Vec::new(),
         }
     }
+}
+
+#[derive(Debug, Serialize)]
+struct Symbol {
+    symbol: char,
+    /// (col, row)
+    position: (usize, usize),
+    /// Indices of all adjacent numbers.
+    numbers: Vec<usize>,
+}
 
-    numbers
+impl Symbol {
+    fn new(symbol: char, position: (usize, usize)) -> Self {
+        Self {
+            symbol,
+            position,
+            numbers: Vec::new(),
+        }
+    }
 }
 
 enum CharType {
-    Number(char),
+    Digit,
     Empty,
     Symbol(char),
 }
@@ -112,7 +237,7 @@ impl From<char> for CharType {
     fn from(value: char) -> Self {
         match value {
             '.' => Self::Empty,
-            c if c.is_numeric() => Self::Number(c),
+            c if c.is_ascii_digit() => Self::Digit,
             other => Self::Symbol(other),
         }
     }
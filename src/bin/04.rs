@@ -1,22 +1,35 @@
-use aoc2023::parse_ws_separated;
+use anyhow::Context;
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::{input, try_parse_ws_separated};
 use std::collections::HashSet;
 
 const INPUT: &str = include_str!("../../input/04");
 const TEST_INPUT: &str = include_str!("../../input/04-test");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[TEST_INPUT], parse_cards);
+
     assert_eq!(part1(TEST_INPUT), 13);
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || parse_cards(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_eq!(part2(TEST_INPUT), 30);
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> u64 {
-    parse_cards(input).map(|card| card.score()).sum()
+    parse_cards(input)
+        .unwrap()
+        .iter()
+        .map(|card| card.score())
+        .sum()
 }
 
 fn part2(input: &str) -> u64 {
-    let mut cards: Vec<Card> = parse_cards(input).collect();
+    let mut cards = parse_cards(input).unwrap();
 
     for i in 0..cards.len() {
         let winning = cards[i].count_winning();
@@ -51,18 +64,22 @@ impl Card {
         }
     }
 
-    fn parse(line: &str) -> Self {
-        let (_id, line) = line.split_once(": ").unwrap();
-        let (winning, owned) = line.split_once(" | ").unwrap();
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let (_id, line) = line.split_once(": ").context("missing ': '")?;
+        let (winning, owned) = line.split_once(" | ").context("missing ' | '")?;
 
-        Self {
+        Ok(Self {
             copies: 1,
-            winning: parse_ws_separated(winning).collect(),
-            owned: parse_ws_separated(owned).collect(),
-        }
+            winning: try_parse_ws_separated(winning)?.into_iter().collect(),
+            owned: try_parse_ws_separated(owned)?.into_iter().collect(),
+        })
     }
 }
 
-fn parse_cards(input: &str) -> impl Iterator<Item = Card> + '_ {
-    input.lines().map(Card::parse)
+fn parse_cards(input: &str) -> anyhow::Result<Vec<Card>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Card::parse(line).with_context(|| format!("line {}", i + 1)))
+        .collect()
 }
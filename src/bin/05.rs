@@ -1,19 +1,66 @@
-use aoc2023::parse_ws_separated;
+use anyhow::{bail, Context};
+use aoc2023::assumptions::{check_if_requested, Assumption};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::{input, option, try_parse_ws_separated};
 use itertools::Itertools;
-use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use std::collections::{HashMap, VecDeque};
+use std::iter;
+use std::ops::Range;
 
 const INPUT: &str = include_str!("../../input/05");
 const TEST_INPUT: &str = include_str!("../../input/05-test");
 
 fn main() {
+    check_if_requested(INPUT, assumptions);
+    fuzz_if_requested(&[TEST_INPUT], Garden::parse);
+    if let Some(seed) = option("--seed") {
+        Garden::parse(&input(INPUT))
+            .unwrap()
+            .trace(seed.parse().unwrap());
+        return;
+    }
+    if let Some(location) = option("--location") {
+        Garden::parse(&input(INPUT))
+            .unwrap()
+            .trace_back(location.parse().unwrap());
+        return;
+    }
+
     assert_eq!(part1(TEST_INPUT), 35);
-    println!("Part 1: {}", part1(INPUT));
+    let garden = Garden::parse(TEST_INPUT).unwrap();
+    assert!((0..200).all(|n| garden.map(n) == garden.map_stepwise(n)));
+    let input = input(INPUT);
+    measure("parse", || Garden::parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_eq!(part2(TEST_INPUT), 46);
-    println!("Part 2: {}", part2(INPUT));
+    assert_eq!(part2_by_locations(TEST_INPUT), 46);
+    let empty_range = TEST_INPUT.replacen("seeds: 79 14", "seeds: 0 0", 1);
+    let without = TEST_INPUT.replacen("seeds: 79 14", "seeds:", 1);
+    assert_eq!(part2(&empty_range), part2(&without));
+    assert_eq!(part2_by_locations(&empty_range), part2(&without));
+    assert!(garden.unmap(46..47).into_iter().flatten().eq([82]));
+    assert!((0..100).all(|location| {
+        let seeds = (0..200).filter(|&seed| garden.map(seed) == location);
+        seeds.eq(garden.unmap(location..location + 1).into_iter().flatten())
+    }));
+    let soil_to_humidity = garden.between("soil", "humidity").unwrap();
+    assert_eq!(soil_to_humidity.map(81), 78);
+    assert!(garden.between("humidity", "soil").is_err());
+    assert!(Garden::parse("seeds: 1\n\na-to-b map:\n0 5 5\n0 8 2").is_err());
+    assert!(Garden::parse("seeds: 1\n\nseed-to-location map:\n10 5 3\n20 5 0").is_ok());
+    assert!(Garden::parse("seeds: 1\n\nseed-to-location map:\n20 5 0\n10 5 3").is_ok());
+    let overlapping = assumptions("seeds: 1 2\n\nseed-to-location map:\n10 5 3\n20 6 3").unwrap();
+    assert!(!overlapping[1].holds);
+    assert!(assumptions("seeds: 1 2\n\nseed-to-location map:\n10 5").is_err());
+    assert!(Garden::parse("seeds: 1\n\nseed-to-a map:\n\na-to-location map:").is_ok());
+    assert!(Garden::parse("seeds: 1\n\nseed-to-a map:\n\nb-to-location map:").is_err());
+    assert!(Garden::parse("seeds: 1\n\nseed-to-a map:\n\na-to-seed map:").is_err());
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> u64 {
-    let garden = Garden::parse(input);
+    let garden = Garden::parse(input).unwrap();
     garden
         .simple_seeds
         .iter()
@@ -24,50 +71,177 @@ fn part1(input: &str) -> u64 {
 }
 
 fn part2(input: &str) -> u64 {
-    let garden = Garden::parse(input);
+    let garden = Garden::parse(input).unwrap();
     garden
         .seed_ranges
-        .par_iter()
-        .flat_map(|range| range.par_iter())
-        .map(|seed| garden.map(seed))
+        .iter()
+        .filter_map(|range| garden.chain.min_in(range.range()))
         .min()
         .unwrap()
 }
 
+/// Go through the locations from the lowest and stop as soon as no lower location can reach a seed.
+fn part2_by_locations(input: &str) -> u64 {
+    let garden = Garden::parse(input).unwrap();
+    let mut lowest = u64::MAX;
+    for range in &garden.chain.invert().ranges {
+        if range.source >= lowest {
+            break;
+        }
+        let locations = range.source..range.source_end();
+        for seeds in garden.seed_ranges.iter().map(SeedRange::range) {
+            if let Some(seeds) = intersection(seeds, range.destination..range.destination_end()) {
+                lowest = lowest.min(locations.start + (seeds.start - range.destination));
+            }
+        }
+    }
+    lowest
+}
+
+/// The parser rejects overlapping ranges, but finds its own way through the maps.
+fn assumptions(input: &str) -> anyhow::Result<Vec<Assumption>> {
+    let mut blocks = input.split("\n\n");
+    let seeds = blocks.next().unwrap_or_default().split_whitespace().skip(1);
+    let maps: Vec<_> = blocks.map(Map::parse_ranges).try_collect()?;
+
+    Ok(vec![
+        Assumption::new(
+            "there is an even number of seeds, so they can form ranges",
+            seeds.count().is_multiple_of(2),
+        ),
+        Assumption::new(
+            "the ranges of every map do not overlap",
+            maps.iter()
+                .all(|(_, _, ranges)| find_overlap(ranges).is_none()),
+        ),
+        Assumption::new(
+            "the maps are listed in order from seed to location",
+            maps.first().is_some_and(|(from, _, _)| from == "seed")
+                && maps.last().is_some_and(|(_, to, _)| to == "location")
+                && maps.iter().tuple_windows().all(|(a, b)| a.1 == b.0),
+        ),
+    ])
+}
+
 #[derive(Debug)]
 struct Garden {
     simple_seeds: Vec<u64>,
     seed_ranges: Vec<SeedRange>,
+    /// Maps in the order of the almanac, which does not matter.
     maps: Vec<Map>,
+    /// All maps composed into one, from seed to location.
+    chain: Map,
 }
 
 impl Garden {
-    fn parse(input: &str) -> Self {
+    /// Fails if the maps form a cycle or do not lead from seed to location.
+    fn parse(input: &str) -> anyhow::Result<Self> {
         let mut blocks = input.split("\n\n");
-        let seeds = blocks.next().unwrap().strip_prefix("seeds: ").unwrap();
-        let maps = blocks.map(Map::parse).collect();
+        let seeds = blocks
+            .next()
+            .unwrap()
+            .strip_prefix("seeds: ")
+            .context("the almanac does not start with seeds")?;
+        let maps: Vec<Map> = blocks.map(Map::parse).try_collect()?;
+        check_acyclic(&maps)?;
 
-        let simple_seeds = parse_ws_separated(seeds).collect_vec();
+        let simple_seeds: Vec<u64> = try_parse_ws_separated(seeds)?;
         let seed_ranges = simple_seeds
             .iter()
             .copied()
             .tuples()
-            .map(|(start, length)| SeedRange { start, length })
-            .collect();
+            .map(|(start, length)| {
+                start
+                    .checked_add(length)
+                    .with_context(|| format!("seed range {start} {length} is too long"))?;
+                Ok(SeedRange { start, length })
+            })
+            .collect::<anyhow::Result<_>>()?;
 
-        Self {
+        let mut garden = Self {
             simple_seeds,
             seed_ranges,
             maps,
+            chain: Map::identity(),
+        };
+        garden.chain = garden.between("seed", "location")?;
+        Ok(garden)
+    }
+
+    /// The maps that lead from one category to the other, with as few steps as possible.
+    fn path(&self, from: &str, to: &str) -> anyhow::Result<Vec<&Map>> {
+        // The map that was used to reach every category.
+        let mut reached_by: HashMap<&str, Option<&Map>> = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            for map in self.maps.iter().filter(|map| map.from == category) {
+                if !reached_by.contains_key(map.to.as_str()) {
+                    reached_by.insert(&map.to, Some(map));
+                    queue.push_back(&map.to);
+                }
+            }
+        }
+
+        if !reached_by.contains_key(to) {
+            bail!("no maps lead from {from} to {to}");
+        }
+        let mut path = Vec::new();
+        let mut category = to;
+        while let Some(map) = reached_by[category] {
+            path.push(map);
+            category = &map.from;
         }
+        path.reverse();
+        Ok(path)
     }
 
-    /// Look up n in every map.
-    fn map(&self, mut n: u64) -> u64 {
-        for map in &self.maps {
-            n = map.map(n);
+    /// All maps on the path between the categories composed into one.
+    fn between(&self, from: &str, to: &str) -> anyhow::Result<Map> {
+        let path = self.path(from, to)?;
+        Ok(path
+            .into_iter()
+            .fold(Map::identity(), |chain, map| chain.then(map)))
+    }
+
+    /// Look up n in the composed map.
+    fn map(&self, n: u64) -> u64 {
+        self.chain.map(n)
+    }
+
+    /// Look up n in every map, one after the other.
+    fn map_stepwise(&self, n: u64) -> u64 {
+        let path = self.path("seed", "location").unwrap();
+        path.into_iter().fold(n, |n, map| map.map(n))
+    }
+
+    /// All seeds that end up in one of the locations.
+    fn unmap(&self, locations: Range<u64>) -> Vec<Range<u64>> {
+        self.chain.preimage(locations)
+    }
+
+    /// Print what the seed becomes in every stage.
+    fn trace(&self, mut n: u64) {
+        for map in self.path("seed", "location").unwrap() {
+            let next = map.map(n);
+            println!("{} {n} -> {} {next}", map.from, map.to);
+            n = next;
+        }
+    }
+
+    /// Print where the location comes from in every stage, there may be several numbers per stage.
+    fn trace_back(&self, location: u64) {
+        let mut numbers = iter::once(location..location + 1).collect_vec();
+        for map in self.path("seed", "location").unwrap().into_iter().rev() {
+            let sources = merge(numbers.iter().flat_map(|range| map.preimage(range.clone())));
+            println!(
+                "{} {} <- {} {}",
+                map.to,
+                format_ranges(&numbers),
+                map.from,
+                format_ranges(&sources)
+            );
+            numbers = sources;
         }
-        n
     }
 }
 
@@ -78,33 +252,257 @@ struct SeedRange {
 }
 
 impl SeedRange {
-    fn par_iter(&self) -> impl ParallelIterator<Item = u64> {
-        let end = self.start + self.length;
-        (self.start..=end).into_par_iter()
+    fn range(&self) -> Range<u64> {
+        self.start..self.start + self.length
     }
 }
 
+/// A piecewise function from one category to another, the ranges are sorted by their source.
+/// The ranges of a map from the almanac cover every number from 0 to `u64::MAX`,
+/// numbers without a range in the almanac are covered by ranges that map them to themselves.
 #[derive(Debug)]
 struct Map {
+    from: String,
+    to: String,
     ranges: Vec<MappedRange>,
 }
 
 impl Map {
+    fn identity() -> Self {
+        Self {
+            from: String::new(),
+            to: String::new(),
+            ranges: vec![MappedRange::identity(0, u64::MAX)],
+        }
+    }
+
+    /// Drop empty ranges, sort the rest and fill the gaps between them. Fails if ranges overlap.
+    fn new(from: String, to: String, mut ranges: Vec<MappedRange>) -> anyhow::Result<Self> {
+        if let Some((a, b)) = find_overlap(&ranges) {
+            bail!("{from}-to-{to} map: the ranges starting at {a} and {b} overlap");
+        }
+        ranges.retain(|range| range.length > 0);
+        ranges.sort_unstable_by_key(|range| range.source);
+
+        let mut complete = Vec::with_capacity(2 * ranges.len() + 1);
+        let mut next = 0;
+        for range in ranges {
+            if range.source > next {
+                complete.push(MappedRange::identity(next, range.source - next));
+            }
+            next = range.source + range.length;
+            complete.push(range);
+        }
+        if next < u64::MAX {
+            complete.push(MappedRange::identity(next, u64::MAX - next));
+        }
+
+        Ok(Self {
+            from,
+            to,
+            ranges: complete,
+        })
+    }
+
     fn map(&self, n: u64) -> u64 {
+        let i = self.ranges.partition_point(|range| range.source_end() <= n);
+        self.ranges
+            .get(i)
+            .and_then(|range| range.map(n))
+            .unwrap_or(n)
+    }
+
+    /// The map that looks up a number in this map and then in `next`.
+    ///
+    /// Every range is split where the ranges of `next` start and end,
+    /// neighbours that end up with the same offset are merged again.
+    fn then(&self, next: &Map) -> Map {
+        let mut ranges: Vec<MappedRange> = Vec::new();
         for range in &self.ranges {
-            if let Some(mapped) = range.map(n) {
-                return mapped;
+            let mut source = range.source;
+            let mut destination = range.destination;
+            let end = range.destination + range.length;
+            while destination < end {
+                let i = next
+                    .ranges
+                    .partition_point(|next| next.source_end() <= destination);
+                let (mapped, length) = match next.ranges.get(i) {
+                    Some(next) => (
+                        next.map(destination).unwrap(),
+                        next.source_end().min(end) - destination,
+                    ),
+                    None => (destination, end - destination),
+                };
+
+                match ranges.last_mut() {
+                    Some(last)
+                        if last.source_end() == source
+                            && last.offset() == offset(source, mapped) =>
+                    {
+                        last.length += length;
+                    }
+                    _ => ranges.push(MappedRange {
+                        destination: mapped,
+                        source,
+                        length,
+                    }),
+                }
+                source += length;
+                destination += length;
+            }
+        }
+
+        Map {
+            from: if self.from.is_empty() {
+                &next.from
+            } else {
+                &self.from
+            }
+            .clone(),
+            to: next.to.clone(),
+            ranges,
+        }
+    }
+
+    /// The map that looks up numbers backwards. Unless the map is one-to-one, the ranges overlap
+    /// and do not cover everything, so use [`Map::image`] to look up numbers in the inverse.
+    fn invert(&self) -> Map {
+        let mut ranges = self.ranges.iter().map(MappedRange::invert).collect_vec();
+        ranges.sort_unstable_by_key(|range| range.source);
+        Map {
+            from: self.to.clone(),
+            to: self.from.clone(),
+            ranges,
+        }
+    }
+
+    /// Everything that the numbers map to, as sorted disjoint ranges.
+    fn image(&self, numbers: Range<u64>) -> Vec<Range<u64>> {
+        merge(self.ranges.iter().filter_map(|range| {
+            let overlap = intersection(range.source..range.source_end(), numbers.clone())?;
+            let offset = overlap.start - range.source;
+            let start = range.destination + offset;
+            Some(start..start + (overlap.end - overlap.start))
+        }))
+    }
+
+    /// Everything that maps to one of the numbers, as sorted disjoint ranges.
+    fn preimage(&self, numbers: Range<u64>) -> Vec<Range<u64>> {
+        self.invert().image(numbers)
+    }
+
+    /// The smallest number that any number in `seeds` maps to, `None` if there are no seeds.
+    /// Every range is increasing, so only the start of every overlap matters.
+    fn min_in(&self, seeds: Range<u64>) -> Option<u64> {
+        if seeds.is_empty() {
+            return None;
+        }
+
+        let first = self
+            .ranges
+            .partition_point(|range| range.source_end() <= seeds.start);
+        self.ranges[first..]
+            .iter()
+            .take_while(|range| range.source < seeds.end)
+            .map(|range| range.map(range.source.max(seeds.start)).unwrap())
+            .min()
+    }
+
+    fn parse(block: &str) -> anyhow::Result<Self> {
+        let (from, to, ranges) = Self::parse_ranges(block)?;
+        Self::new(from, to, ranges)
+    }
+
+    /// The categories and the ranges of a map block as they are listed, without checking them.
+    fn parse_ranges(block: &str) -> anyhow::Result<(String, String, Vec<MappedRange>)> {
+        let mut lines = block.lines();
+        let header = lines.next().context("empty map")?;
+        let (from, to) = header
+            .strip_suffix(" map:")
+            .and_then(|name| name.split_once("-to-"))
+            .with_context(|| format!("{header:?} is not a map header"))?;
+        let ranges = lines.map(MappedRange::parse).try_collect()?;
+        Ok((from.to_string(), to.to_string(), ranges))
+    }
+}
+
+/// The sources of two ranges that overlap, if there are any. Empty ranges overlap nothing.
+fn find_overlap(ranges: &[MappedRange]) -> Option<(u64, u64)> {
+    ranges
+        .iter()
+        .filter(|range| range.length > 0)
+        .sorted_unstable_by_key(|range| range.source)
+        .tuple_windows()
+        .find(|(a, b)| a.source_end() > b.source)
+        .map(|(a, b)| (a.source, b.source))
+}
+
+/// How far a number moves, without overflowing for any pair of u64.
+fn offset(source: u64, destination: u64) -> i128 {
+    i128::from(destination) - i128::from(source)
+}
+
+/// Fail if following the maps can lead back to a category.
+fn check_acyclic(maps: &[Map]) -> anyhow::Result<()> {
+    // Remove categories that no map leads to, until none are left.
+    let mut incoming: HashMap<&str, usize> = HashMap::new();
+    for map in maps {
+        incoming.entry(&map.from).or_default();
+        *incoming.entry(&map.to).or_default() += 1;
+    }
+    let mut sources = incoming
+        .iter()
+        .filter(|(_, &count)| count == 0)
+        .map(|(&category, _)| category)
+        .collect_vec();
+    while let Some(category) = sources.pop() {
+        incoming.remove(category);
+        for map in maps.iter().filter(|map| map.from == category) {
+            let count = incoming.get_mut(map.to.as_str()).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                sources.push(&map.to);
             }
         }
+    }
 
-        n
+    if let Some(category) = incoming.keys().sorted().next() {
+        bail!("the maps form a cycle that leads to {category}");
     }
+    Ok(())
+}
+
+fn intersection(a: Range<u64>, b: Range<u64>) -> Option<Range<u64>> {
+    let overlap = a.start.max(b.start)..a.end.min(b.end);
+    (!overlap.is_empty()).then_some(overlap)
+}
 
-    fn parse(block: &str) -> Self {
-        let lines = block.lines().skip(1);
-        let ranges = lines.map(MappedRange::parse).collect();
-        Self { ranges }
+/// Sort the ranges and join the ones that overlap or touch.
+fn merge(ranges: impl IntoIterator<Item = Range<u64>>) -> Vec<Range<u64>> {
+    let mut ranges = ranges.into_iter().collect_vec();
+    ranges.sort_unstable_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if last.end >= range.start => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
     }
+    merged
+}
+
+/// `a..b` or just `a` for ranges of one number.
+fn format_ranges(ranges: &[Range<u64>]) -> String {
+    ranges
+        .iter()
+        .map(|range| {
+            if range.end - range.start == 1 {
+                range.start.to_string()
+            } else {
+                format!("{}..{}", range.start, range.end)
+            }
+        })
+        .join(", ")
 }
 
 #[derive(Debug)]
@@ -115,6 +513,34 @@ struct MappedRange {
 }
 
 impl MappedRange {
+    fn identity(start: u64, length: u64) -> Self {
+        Self {
+            destination: start,
+            source: start,
+            length,
+        }
+    }
+
+    fn source_end(&self) -> u64 {
+        self.source + self.length
+    }
+
+    fn destination_end(&self) -> u64 {
+        self.destination + self.length
+    }
+
+    fn invert(&self) -> Self {
+        Self {
+            destination: self.source,
+            source: self.destination,
+            length: self.length,
+        }
+    }
+
+    fn offset(&self) -> i128 {
+        offset(self.source, self.destination)
+    }
+
     fn map(&self, n: u64) -> Option<u64> {
         let source_start = self.source;
         let source_end = source_start + self.length;
@@ -125,13 +551,19 @@ impl MappedRange {
         }
     }
 
-    fn parse(line: &str) -> Self {
-        let [destination, source, length] =
-            parse_ws_separated(line).collect_vec().try_into().unwrap();
-        Self {
+    /// Fails unless there are three numbers and both ranges fit into u64.
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let Ok([destination, source, length]) = <[u64; 3]>::try_from(try_parse_ws_separated(line)?)
+        else {
+            bail!("{line:?} is not a destination, a source and a length");
+        };
+        if source.checked_add(length).is_none() || destination.checked_add(length).is_none() {
+            bail!("the ranges of {line:?} are too long");
+        }
+        Ok(Self {
             destination,
             source,
             length,
-        }
+        })
     }
 }
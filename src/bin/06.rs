@@ -1,71 +1,178 @@
-use aoc2023::{concat, parse_ws_separated};
-use itertools::Itertools;
+use anyhow::{bail, Context};
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::{input, try_parse_ws_separated, Widened};
 use std::ops::RangeInclusive;
 
 const INPUT: &str = include_str!("../../input/06");
 const TEST_INPUT: &str = include_str!("../../input/06-test");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[TEST_INPUT], parse);
+
     assert_eq!(part1(TEST_INPUT), 288);
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_eq!(part2(TEST_INPUT), 71503);
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
-fn part1(input: &str) -> u64 {
-    parse(input).map(how_to_beat).map(len).product()
+fn part1(input: &str) -> u128 {
+    parse(input)
+        .unwrap()
+        .into_iter()
+        .map(how_to_beat)
+        .map(len)
+        .product()
 }
 
-fn part2(input: &str) -> u64 {
-    let race = parse(input).reduce(Race::concat).unwrap();
+fn part2(input: &str) -> u128 {
+    let race = parse(input)
+        .unwrap()
+        .into_iter()
+        .reduce(Race::concat)
+        .unwrap();
     let range = how_to_beat(race);
     len(range)
 }
 
 /// Range of time the button might be pressed to beat a record.
-fn how_to_beat(race: Race) -> RangeInclusive<u64> {
-    let time = race.time as f64;
-    let record = race.record as f64;
+fn how_to_beat(race: Race) -> RangeInclusive<u128> {
+    let time = u128::from(race.time);
+    let record = u128::from(race.record);
 
     // The distance travelled d is dependant on the time t the button is pressed: d = time*t - t^2.
-    // Solve d = record for min and max t:
-    let offset = time * 0.5;
-    let add = ((-time * 0.5).powf(2.0) - record).sqrt();
-    let min = offset - add;
-    let max = offset + add;
+    // This is a parabola with its peak at time/2, so the times that beat the record
+    // are a contiguous range around the peak. Search for both ends with exact integer arithmetic,
+    // because floating point is not precise enough for big races.
+    let beats = |t: u128| t.checked_mul(time - t).is_none_or(|d| d > record);
+    let peak = time / 2;
+    if !beats(peak) {
+        return peak + 1..=peak;
+    }
 
-    // Shrink the interval to only return times to beat the record.
-    let min = min.floor() as u64 + 1;
-    let max = max.ceil() as u64 - 1;
+    let min = partition_point(0, peak, |t| !beats(t));
+    let max = partition_point(peak, time, beats) - 1;
 
     min..=max
 }
 
-fn len(range: RangeInclusive<u64>) -> u64 {
-    range.try_len().unwrap() as u64
+/// Find the first number in `start..=end` for which the predicate is false,
+/// assuming it is true for a prefix of the range and false for the rest.
+fn partition_point(mut start: u128, mut end: u128, pred: impl Fn(u128) -> bool) -> u128 {
+    while start < end {
+        let mid = start + (end - start) / 2;
+        if pred(mid) {
+            start = mid + 1;
+        } else {
+            end = mid;
+        }
+    }
+    start
+}
+
+fn len(range: RangeInclusive<u128>) -> u128 {
+    range.end() + 1 - range.start()
 }
 
 struct Race {
-    time: u64,
-    record: u64,
+    time: Widened,
+    record: Widened,
 }
 
 impl Race {
     fn concat(self, other: Self) -> Self {
         Self {
-            time: concat(self.time, other.time),
-            record: concat(self.record, other.record),
+            time: self.time.concat(other.time).expect("time fits into u128"),
+            record: self
+                .record
+                .concat(other.record)
+                .expect("record fits into u128"),
         }
     }
 }
 
-fn parse(input: &str) -> impl Iterator<Item = Race> + '_ {
+fn parse(input: &str) -> anyhow::Result<Vec<Race>> {
     let mut lines = input.lines();
-    let mut parse_next_line =
-        |prefix: &str| parse_ws_separated(lines.next().unwrap().trim_start_matches(prefix));
-    let times = parse_next_line("Time:      ");
-    let records = parse_next_line("Distance:  ");
-    times
-        .zip(records)
-        .map(|(time, record)| Race { time, record })
+    let mut parse_next_line = |prefix: &str| -> anyhow::Result<Vec<u64>> {
+        let line = lines.next().with_context(|| format!("missing {prefix}"))?;
+        let numbers = line
+            .strip_prefix(prefix)
+            .with_context(|| format!("{line:?} does not start with {prefix}"))?;
+        try_parse_ws_separated(numbers)
+    };
+    let times = parse_next_line("Time:")?;
+    let records = parse_next_line("Distance:")?;
+    if times.len() != records.len() {
+        bail!("{} times but {} records", times.len(), records.len());
+    }
+
+    let races = times.into_iter().zip(records).map(|(time, record)| Race {
+        time: time.into(),
+        record: record.into(),
+    });
+    Ok(races.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2023::differential::compare;
+    use aoc2023::generate;
+    use aoc2023::random::Rng;
+    use itertools::Itertools;
+
+    /// Try every time the button can be held.
+    fn count_wins(race: Race) -> u128 {
+        let (time, record) = (u128::from(race.time), u128::from(race.record));
+        (0..=time).filter(|t| t * (time - t) > record).count() as u128
+    }
+
+    /// Every version of the races with one race removed.
+    fn shrink_races(input: &str) -> Vec<String> {
+        let [times, records] = input
+            .lines()
+            .map(|line| line.split_whitespace().collect_vec())
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        (1..times.len())
+            .map(|skip| {
+                let without = |line: &[&str]| {
+                    let (label, numbers) = line.split_first().unwrap();
+                    let numbers = numbers
+                        .iter()
+                        .enumerate()
+                        .filter(|&(i, _)| i + 1 != skip)
+                        .map(|(_, n)| n)
+                        .join(" ");
+                    format!("{label:<11}{numbers}\n")
+                };
+                without(&times) + &without(&records)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn binary_search_matches_brute_force() {
+        let generate = |rng: &mut Rng| {
+            let races = 1 + rng.below(4);
+            generate::races(rng, races, 1000)
+        };
+        let reference = |input: &String| {
+            parse(input)
+                .unwrap()
+                .into_iter()
+                .map(count_wins)
+                .product::<u128>()
+        };
+        let fast = |input: &String| part1(input);
+
+        if let Err(disagreement) = compare(2000, generate, |i| shrink_races(i), reference, fast) {
+            panic!("{disagreement}");
+        }
+    }
 }
@@ -1,102 +1,248 @@
+use anyhow::{bail, Context};
+use aoc2023::allocations;
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::random::Rng;
+use aoc2023::{flag, input};
 use itertools::Itertools;
 use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::iter;
+use std::time::Instant;
 
 const INPUT: &str = include_str!("../../input/07");
 const TEST_INPUT: &str = include_str!("../../input/07-test");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[TEST_INPUT], |input| {
+        parse(input, &Rules::camel_cards_with_jokers())
+    });
+    if flag("--bench") {
+        bench();
+        return;
+    }
+
     assert_eq!(part1(TEST_INPUT), 6440, "Part 1");
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || parse(&input, &Rules::camel_cards()).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_eq!(part2(TEST_INPUT), 5905, "Part 2");
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> usize {
-    solve(input, false)
+    solve(input, &Rules::camel_cards())
 }
 
 fn part2(input: &str) -> usize {
-    solve(input, true)
+    solve(input, &Rules::camel_cards_with_jokers())
 }
 
-fn solve(input: &str, jokers_are_wildcards: bool) -> usize {
-    parse(input, jokers_are_wildcards)
+fn solve(input: &str, rules: &Rules) -> usize {
+    parse(input, rules)
+        .unwrap()
+        .into_iter()
         .sorted()
         .enumerate()
         .map(|(rank0, hand)| (rank0 + 1) * hand.bid)
         .sum()
 }
 
-#[derive(Eq, Debug)]
-struct Hand {
-    cards: [Card; 5],
-    bid: usize,
+/// Compare sorting by the precomputed strength with computing the kind in every comparison.
+fn bench() {
+    const HANDS: usize = 1_000_000;
+
+    let mut rng = Rng::new(7);
+    let rules = Rules::camel_cards_with_jokers();
+    let input = (0..HANDS)
+        .map(|_| {
+            let cards: String = (0..rules.hand_size)
+                .map(|_| rules.ranking[rng.below(rules.ranking.len())])
+                .collect();
+            format!("{cards} {}", rng.below(1000) + 1)
+        })
+        .join("\n");
+
+    let start = Instant::now();
+    let (hands, allocations) = allocations::count(|| parse(&input, &rules).unwrap());
+    println!("Parse {HANDS} hands: {:?}", start.elapsed());
+    if allocations::enabled() {
+        println!("    {allocations}");
+    }
+
+    let mut by_kind = hands.iter().collect_vec();
+    let start = Instant::now();
+    by_kind.sort_by(|a, b| {
+        let kind = rules.kind(&a.cards).cmp(&rules.kind(&b.cards));
+        kind.then_with(|| a.cards.cmp(&b.cards))
+    });
+    println!(
+        "Sort computing the kind per comparison: {:?}",
+        start.elapsed()
+    );
+
+    let mut by_strength = hands.iter().collect_vec();
+    let start = Instant::now();
+    by_strength.sort();
+    println!("Sort by precomputed strength: {:?}", start.elapsed());
+
+    assert_eq!(by_kind, by_strength);
 }
 
-impl Hand {
-    fn kind(&self) -> HandKind {
-        let counted = self
-            .cards
-            .iter()
-            .filter(|card| !card.is_wildcard())
-            .counts_by(|card| card.symbol);
-
-        let wildcards = self.cards.iter().filter(|card| card.is_wildcard()).count();
-        let same_cards = counted.values().copied().max().unwrap_or(0);
-        let pairs = counted.values().copied().filter(|&c| c == 2).count();
-
-        if same_cards == 5
-            || same_cards == 4 && wildcards == 1
-            || same_cards == 3 && wildcards == 2
-            || pairs == 1 && wildcards == 3
-            || wildcards >= 4
-        {
-            HandKind::Five
-        } else if same_cards == 4
-            || same_cards == 3 && wildcards == 1
-            || pairs == 1 && wildcards == 2
-            || wildcards >= 3
-        {
-            HandKind::Four
-        } else if same_cards == 3 && pairs == 1
-            || pairs == 2 && wildcards == 1
-            || pairs == 1 && wildcards == 2
-        {
-            HandKind::FullHouse
-        } else if same_cards == 3 || pairs == 1 && wildcards == 1 || wildcards == 2 {
-            HandKind::Three
-        } else if pairs == 2 || wildcards == 2 {
-            HandKind::TwoPair
-        } else if pairs == 1 || wildcards == 1 {
-            HandKind::OnePair
-        } else {
-            HandKind::HighCard
+/// Everything that decides how hands are compared.
+#[derive(Debug, Eq, PartialEq)]
+struct Rules {
+    hand_size: usize,
+    /// Card symbols from weakest to strongest.
+    ranking: Vec<char>,
+    /// Cards that pretend to be whatever card makes the hand strongest.
+    wildcards: Vec<char>,
+    /// Hand categories from weakest to strongest.
+    categories: Vec<Category>,
+}
+
+impl Rules {
+    fn camel_cards() -> Self {
+        Self {
+            hand_size: 5,
+            ranking: "23456789TJQKA".chars().collect(),
+            wildcards: Vec::new(),
+            categories: vec![
+                // High card
+                Category::new(&[]),
+                Category::new(&[2]),
+                Category::new(&[2, 2]),
+                Category::new(&[3]),
+                Category::new(&[3, 2]),
+                Category::new(&[4]),
+                Category::new(&[5]),
+            ],
+        }
+    }
+
+    fn camel_cards_with_jokers() -> Self {
+        Self {
+            ranking: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..Self::camel_cards()
         }
     }
 
-    fn parse(line: &str, jokers_are_wildcards: bool) -> Self {
-        let (cards, bid) = line.split_once(' ').unwrap();
-        let cards = cards
+    fn card(&self, symbol: char) -> anyhow::Result<Card> {
+        let rank = self
+            .ranking
+            .iter()
+            .position(|&s| s == symbol)
+            .with_context(|| format!("unknown card symbol '{symbol}'"))?;
+        Ok(Card {
+            symbol,
+            rank,
+            wildcard: self.wildcards.contains(&symbol),
+        })
+    }
+
+    /// Find the strongest category the cards can form with the best use of the wildcards.
+    fn kind(&self, cards: &[Card]) -> Kind {
+        let wildcards = cards.iter().filter(|card| card.wildcard).count();
+        let signature = cards
+            .iter()
+            .filter(|card| !card.wildcard)
+            .counts_by(|card| card.symbol)
+            .into_values()
+            .sorted_unstable_by(|a, b| b.cmp(a))
+            .collect_vec();
+
+        let strongest = self
+            .categories
+            .iter()
+            .rposition(|category| category.missing_cards(&signature) <= wildcards)
+            .expect("cards match no category, add one with an empty signature");
+
+        Kind(strongest)
+    }
+
+    /// Pack the kind and the rank of every card into one number that sorts like the hand.
+    fn strength(&self, cards: &[Card]) -> u64 {
+        let bits_for = |n: usize| usize::BITS - n.saturating_sub(1).leading_zeros();
+        let card_bits = bits_for(self.ranking.len());
+        let kind_bits = bits_for(self.categories.len());
+        assert!(
+            kind_bits + card_bits * cards.len() as u32 <= u64::BITS,
+            "hand does not fit into the sort key"
+        );
+
+        let kind = self.kind(cards).0 as u64;
+        cards
+            .iter()
+            .fold(kind, |key, card| key << card_bits | card.rank as u64)
+    }
+}
+
+/// A hand category is described by the sizes of the groups of equal cards it requires,
+/// for example `[3, 2]` for a full house.
+#[derive(Debug, Eq, PartialEq)]
+struct Category {
+    /// Sorted from biggest to smallest group.
+    signature: Vec<usize>,
+}
+
+impl Category {
+    fn new(signature: &[usize]) -> Self {
+        let signature = signature
+            .iter()
+            .copied()
+            .sorted_unstable_by(|a, b| b.cmp(a))
+            .collect();
+        Self { signature }
+    }
+
+    /// How many wildcards are needed to turn a hand with the given signature into this category.
+    ///
+    /// Pairing the biggest required group with the biggest group of cards
+    /// (and so on) needs the fewest wildcards.
+    fn missing_cards(&self, signature: &[usize]) -> usize {
+        let available = signature.iter().copied().chain(iter::repeat(0));
+        self.signature
+            .iter()
+            .zip(available)
+            .map(|(&required, available)| required.saturating_sub(available))
+            .sum()
+    }
+}
+
+#[derive(Debug)]
+struct Hand {
+    cards: Vec<Card>,
+    bid: usize,
+    /// Sort key from [`Rules::strength`].
+    strength: u64,
+}
+
+impl Hand {
+    fn parse(line: &str, rules: &Rules) -> anyhow::Result<Self> {
+        let (cards, bid) = line.split_once(' ').context("missing bid")?;
+        let cards: Vec<Card> = cards
             .chars()
-            .map(|symbol| Card {
-                symbol,
-                jokers_are_wildcards,
-            })
-            .collect_vec()
-            .try_into()
-            .unwrap();
-        let bid = bid.parse().unwrap();
-        Self { cards, bid }
+            .map(|symbol| rules.card(symbol))
+            .try_collect()?;
+        if cards.len() != rules.hand_size {
+            bail!("wrong hand size: {line}");
+        }
+        let bid = bid.parse()?;
+        let strength = rules.strength(&cards);
+        Ok(Self {
+            cards,
+            bid,
+            strength,
+        })
     }
 }
 
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.kind().cmp(&other.kind()) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Equal => self.cards.iter().cmp(&other.cards),
-        }
+        self.strength.cmp(&other.strength)
     }
 }
 
@@ -106,56 +252,37 @@ impl PartialOrd for Hand {
     }
 }
 
-impl PartialEq<Self> for Hand {
+/// Hands are equal if they have the same cards, the bid does not matter.
+/// This agrees with [`Ord`], because the strength is unique for every combination of cards.
+impl PartialEq for Hand {
     fn eq(&self, other: &Self) -> bool {
-        self.cmp(other).is_eq()
+        self.cards == other.cards
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
-enum HandKind {
-    HighCard,
-    OnePair,
-    TwoPair,
-    Three,
-    FullHouse,
-    Four,
-    Five,
+impl Eq for Hand {}
+
+impl Hash for Hand {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cards.hash(state);
+    }
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Index of a category in [`Rules::categories`], so stronger kinds compare greater.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+struct Kind(usize);
+
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
 struct Card {
     symbol: char,
-    jokers_are_wildcards: bool,
-}
-
-impl Card {
-    fn is_wildcard(&self) -> bool {
-        self.jokers_are_wildcards && self.symbol == 'J'
-    }
+    /// Position in [`Rules::ranking`].
+    rank: usize,
+    wildcard: bool,
 }
 
 impl Ord for Card {
     fn cmp(&self, other: &Self) -> Ordering {
-        let order = |card: &Card| match card.symbol {
-            'A' => 14,
-            'K' => 13,
-            'Q' => 12,
-            'J' if card.jokers_are_wildcards => 1,
-            'J' => 11,
-            'T' => 10,
-            '9' => 9,
-            '8' => 8,
-            '7' => 7,
-            '6' => 6,
-            '5' => 5,
-            '4' => 4,
-            '3' => 3,
-            '2' => 2,
-            other => panic!("unknown card symbol '{other}'"),
-        };
-
-        order(self).cmp(&order(other))
+        self.rank.cmp(&other.rank)
     }
 }
 
@@ -165,8 +292,90 @@ impl PartialOrd for Card {
     }
 }
 
-fn parse(input: &str, jokers_are_wildcards: bool) -> impl Iterator<Item = Hand> + '_ {
+fn parse(input: &str, rules: &Rules) -> anyhow::Result<Vec<Hand>> {
     input
         .lines()
-        .map(move |l| Hand::parse(l, jokers_are_wildcards))
+        .enumerate()
+        .map(|(i, line)| Hand::parse(line, rules).with_context(|| format!("line {}", i + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Classify hands without wildcards by their sorted counts, independent of [`Category`].
+    fn plain_kind(symbols: &[char]) -> Kind {
+        let counts = symbols
+            .iter()
+            .sorted_unstable()
+            .dedup_with_count()
+            .map(|(count, _)| count)
+            .sorted_unstable_by(|a, b| b.cmp(a))
+            .collect_vec();
+        let kind = match counts.as_slice() {
+            [5] => 6,
+            [4, 1] => 5,
+            [3, 2] => 4,
+            [3, 1, 1] => 3,
+            [2, 2, 1] => 2,
+            [2, 1, 1, 1] => 1,
+            [1, 1, 1, 1, 1] => 0,
+            other => panic!("not a hand of five: {other:?}"),
+        };
+        Kind(kind)
+    }
+
+    /// Try every substitution for every wildcard and keep the best kind.
+    fn brute_force_kind(rules: &Rules, symbols: &mut [char], from: usize) -> Kind {
+        let Some(wildcard) = (from..symbols.len()).find(|&i| rules.wildcards.contains(&symbols[i]))
+        else {
+            return plain_kind(symbols);
+        };
+
+        let original = symbols[wildcard];
+        let best = rules
+            .ranking
+            .iter()
+            .map(|&substitute| {
+                symbols[wildcard] = substitute;
+                brute_force_kind(rules, symbols, wildcard + 1)
+            })
+            .max()
+            .unwrap();
+        symbols[wildcard] = original;
+        best
+    }
+
+    /// Compare the kind of all 13^5 hands with the brute force oracle and return the mismatches.
+    fn mismatches(rules: &Rules) -> Vec<String> {
+        (0..rules.hand_size)
+            .map(|_| rules.ranking.iter().copied())
+            .multi_cartesian_product()
+            .filter_map(|mut symbols| {
+                let cards = symbols
+                    .iter()
+                    .map(|&s| rules.card(s).unwrap())
+                    .collect_vec();
+                let kind = rules.kind(&cards);
+                let expected = brute_force_kind(rules, &mut symbols, 0);
+                (kind != expected).then(|| {
+                    let hand: String = symbols.iter().collect();
+                    format!("{hand}: got {kind:?}, expected {expected:?}")
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn all_hands_without_wildcards() {
+        let mismatches = mismatches(&Rules::camel_cards());
+        assert!(mismatches.is_empty(), "{mismatches:#?}");
+    }
+
+    #[test]
+    fn all_hands_with_jokers_as_wildcards() {
+        let mismatches = mismatches(&Rules::camel_cards_with_jokers());
+        assert!(mismatches.is_empty(), "{mismatches:#?}");
+    }
 }
@@ -1,33 +1,91 @@
-use aoc2023::lcm;
-use std::collections::HashMap;
+use anyhow::{bail, Context};
+use aoc2023::assumptions::{check_if_requested, Assumption};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::{input, lcm};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
 
 const INPUT: &str = include_str!("../../input/08");
 const TEST_INPUT_1: &str = include_str!("../../input/08-test-1");
 const TEST_INPUT_2: &str = include_str!("../../input/08-test-2");
 
 fn main() {
+    check_if_requested(INPUT, assumptions);
+    fuzz_if_requested(&[TEST_INPUT_1, TEST_INPUT_2], |input| {
+        let map = Map::parse(input)?;
+        map.count_steps("AAA".to_string(), |location| location == "ZZZ");
+        Ok(())
+    });
+
     assert_eq!(part1(TEST_INPUT_1), 6, "Part 1");
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || Map::parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_eq!(part2(TEST_INPUT_2), 6, "Part 2");
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> usize {
     let start = "AAA".to_string();
     let end = |location: &str| location == "ZZZ";
-    Map::parse(input).count_steps(start, end)
+    Map::parse(input)
+        .unwrap()
+        .count_steps(start, end)
+        .expect("ZZZ can be reached from AAA")
 }
 
 fn part2(input: &str) -> usize {
-    let map = Map::parse(input);
+    let map = Map::parse(input).unwrap();
     let end = |location: &str| location.ends_with('Z');
 
     map.starting_locations()
-        .map(|start| map.count_steps(start, end))
+        .map(|start| {
+            map.count_steps(start, end)
+                .expect("every ghost reaches an end")
+        })
         .reduce(lcm)
         .unwrap()
 }
 
+/// Part 2 uses the least common multiple of the steps each ghost needs,
+/// which is only right if every ghost walks in a clean cycle through a single end.
+fn assumptions(input: &str) -> anyhow::Result<Vec<Assumption>> {
+    let map = Map::parse(input)?;
+    let ghosts = map
+        .starting_locations()
+        .map(|start| {
+            map.ends(&start, |location| location.ends_with('Z'))
+                .take(2)
+                .collect_vec()
+        })
+        .collect_vec();
+    let reachable = map
+        .ends("AAA", |location| location == "ZZZ")
+        .next()
+        .is_some();
+
+    Ok(vec![
+        Assumption::new(
+            "ZZZ can be reached from AAA",
+            reachable,
+        ),
+        Assumption::new(
+            "ghosts reach ends only after complete passes through the instructions",
+            ghosts
+                .iter()
+                .flatten()
+                .all(|(steps, _)| steps % map.instructions.len() == 0),
+        ),
+        Assumption::new(
+            "every ghost reaches the same end again after as many steps as it took to get there",
+            ghosts.iter().all(|ends| {
+                matches!(&ends[..], [(first, a), (second, b)] if *second == 2 * first && a == b)
+            }),
+        ),
+    ])
+}
+
 struct Map {
     transitions: HashMap<String, Directions>,
     instructions: Vec<Instruction>,
@@ -59,35 +117,83 @@ impl Map {
             .map(|k| k.to_string())
     }
 
-    fn count_steps(&self, start: String, end: impl Fn(&str) -> bool) -> usize {
+    /// The number of steps after which an end is reached and the end, following single instructions.
+    /// Stops after every pair of location and instruction could have been visited twice.
+    fn ends<'a>(
+        &'a self,
+        start: &str,
+        end: impl Fn(&str) -> bool + 'a,
+    ) -> impl Iterator<Item = (usize, String)> + 'a {
+        let limit = 2 * self.transitions.len() * self.instructions.len();
+        let mut location = start.to_string();
+        self.instructions
+            .iter()
+            .cycle()
+            .take(limit)
+            .map_while(move |&instruction| {
+                let directions = self.transitions.get(&location)?;
+                location = match instruction {
+                    Instruction::Left => directions.left.clone(),
+                    Instruction::Right => directions.right.clone(),
+                };
+                Some(location.clone())
+            })
+            .enumerate()
+            .filter(move |(_, location)| end(location))
+            .map(|(i, location)| (i + 1, location))
+    }
+
+    /// Follow the instructions until an end is reached after a complete pass through them.
+    /// Returns `None` if that never happens, which is noticed once a location repeats after a pass.
+    fn count_steps(&self, start: String, end: impl Fn(&str) -> bool) -> Option<usize> {
+        if !self.transitions.contains_key(&start) {
+            return None;
+        }
+
         let mut location = start;
         let mut steps = 0;
+        let mut visited = HashSet::new();
 
         while !end(&location) {
+            if !visited.insert(location.clone()) {
+                return None;
+            }
             location = self.follow(location);
             steps += self.instructions.len();
         }
 
-        steps
+        Some(steps)
     }
 
-    fn parse(input: &str) -> Self {
+    /// Fails if there are no instructions or a direction leads to an unknown location.
+    fn parse(input: &str) -> anyhow::Result<Self> {
         let mut lines = input.lines();
-        let instructions = lines
+        let instructions: Vec<_> = lines
             .next()
-            .unwrap()
+            .context("missing instructions")?
             .chars()
             .map(Instruction::parse)
-            .collect();
-        let transitions = lines
+            .try_collect()?;
+        if instructions.is_empty() {
+            bail!("there are no instructions");
+        }
+        let transitions: HashMap<_, _> = lines
             .skip(1)
-            .map(Transition::parse)
-            .map(|t| (t.from, t.directions))
-            .collect();
-        Self {
+            .map(|line| Transition::parse(line).map(|t| (t.from, t.directions)))
+            .try_collect()?;
+
+        let destinations = transitions.values().flat_map(|d| [&d.left, &d.right]);
+        if let Some(unknown) = destinations
+            .into_iter()
+            .find(|&destination| !transitions.contains_key(destination))
+        {
+            bail!("there is no way on from {unknown}");
+        }
+
+        Ok(Self {
             instructions,
             transitions,
-        }
+        })
     }
 }
 
@@ -97,18 +203,16 @@ struct Directions {
 }
 
 impl Directions {
-    fn parse(tuple: &str) -> Self {
+    fn parse(tuple: &str) -> anyhow::Result<Self> {
         let (left, right) = tuple
             .strip_prefix('(')
-            .unwrap()
-            .strip_suffix(')')
-            .unwrap()
-            .split_once(", ")
-            .unwrap();
-        Self {
+            .and_then(|tuple| tuple.strip_suffix(')'))
+            .and_then(|tuple| tuple.split_once(", "))
+            .with_context(|| format!("{tuple:?} is not a pair of locations"))?;
+        Ok(Self {
             left: left.to_string(),
             right: right.to_string(),
-        }
+        })
     }
 }
 
@@ -119,11 +223,11 @@ enum Instruction {
 }
 
 impl Instruction {
-    fn parse(c: char) -> Self {
+    fn parse(c: char) -> anyhow::Result<Self> {
         match c {
-            'L' => Self::Left,
-            'R' => Self::Right,
-            other => panic!("unknown instruction '{other}'"),
+            'L' => Ok(Self::Left),
+            'R' => Ok(Self::Right),
+            other => bail!("unknown instruction '{other}'"),
         }
     }
 }
@@ -134,11 +238,11 @@ struct Transition {
 }
 
 impl Transition {
-    fn parse(line: &str) -> Self {
-        let (from, directions) = line.split_once(" = ").unwrap();
-        Self {
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let (from, directions) = line.split_once(" = ").context("missing ' = '")?;
+        Ok(Self {
             from: from.to_string(),
-            directions: Directions::parse(directions),
-        }
+            directions: Directions::parse(directions)?,
+        })
     }
 }
@@ -1,49 +1,86 @@
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::polynomial::Polynomial;
 use aoc2023::*;
 
 const INPUT: &str = include_str!("../../input/09");
 const TEST_INPUT: &str = include_str!("../../input/09-test");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[TEST_INPUT], parse);
+
     assert_eq!(part1(TEST_INPUT), 114, "Part 1");
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_eq!(part2(TEST_INPUT), 2, "Part 2");
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
-fn part1(input: &str) -> i64 {
-    input.lines().map(parse_ws_separated).map(extrapolate).sum()
+fn part1(input: &str) -> i128 {
+    parse(input)
+        .unwrap()
+        .into_iter()
+        .map(|values| Polynomial::fit(values.iter().copied()).at(values.len() as i64))
+        .sum()
 }
 
-fn part2(input: &str) -> i64 {
-    input
-        .lines()
-        .map(parse_ws_separated)
-        .map(|numbers| numbers.rev())
-        .map(extrapolate)
+fn part2(input: &str) -> i128 {
+    parse(input)
+        .unwrap()
+        .into_iter()
+        .map(|values| Polynomial::fit(values).at(-1))
         .sum()
 }
 
-fn extrapolate(mut input: impl Iterator<Item = i64>) -> i64 {
-    let start = input.next().unwrap();
+fn parse(input: &str) -> anyhow::Result<Vec<Vec<i64>>> {
+    input.lines().map(try_parse_ws_separated).collect()
+}
 
-    // We do not need to store all values,
-    // only the difference values are important.
-    let mut differences = vec![start];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2023::differential::{compare, shrink_lines};
+    use aoc2023::generate;
+    use aoc2023::random::Rng;
+    use itertools::Itertools;
 
-    // Build the list of differences
-    for next in input {
-        let mut propagate_diff = next;
-        for diff in &mut differences {
-            let previous = *diff;
-            *diff = propagate_diff;
-            propagate_diff -= previous;
-        }
-        let double_0 = *differences.last().unwrap() == 0 && propagate_diff == 0;
-        if !double_0 {
-            differences.push(propagate_diff);
+    /// Extrapolate in both directions with the whole difference table, like the puzzle describes it.
+    fn extrapolate(values: &[i64]) -> (i128, i128) {
+        let mut rows = vec![values.iter().map(|&v| i128::from(v)).collect_vec()];
+        while rows.last().unwrap().iter().any(|&v| v != 0) {
+            let row = rows.last().unwrap();
+            rows.push(row.iter().tuple_windows().map(|(a, b)| b - a).collect());
         }
+
+        let next = rows.iter().filter_map(|row| row.last()).sum();
+        let previous = rows
+            .iter()
+            .rev()
+            .filter_map(|row| row.first())
+            .fold(0, |below, first| first - below);
+        (next, previous)
     }
 
-    // Extrapolate
-    differences.into_iter().sum()
+    #[test]
+    fn polynomial_matches_difference_table() {
+        let generate = |rng: &mut Rng| {
+            let (lines, length) = (1 + rng.below(5), rng.below(12));
+            generate::sequences(rng, lines, length)
+        };
+        let reference = |input: &String| {
+            parse(input)
+                .unwrap()
+                .iter()
+                .map(|values| extrapolate(values))
+                .fold((0, 0), |(a, b), (next, previous)| (a + next, b + previous))
+        };
+        let fast = |input: &String| (part1(input), part2(input));
+
+        if let Err(disagreement) = compare(2000, generate, |i| shrink_lines(i), reference, fast) {
+            panic!("{disagreement}");
+        }
+    }
 }
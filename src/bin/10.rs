@@ -1,26 +1,80 @@
-use aoc2023::{assert_example, Vec2};
-use std::collections::HashMap;
+use anyhow::bail;
+use aoc2023::assumptions::{check_if_requested, Assumption};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::render::{Picture, Rgb};
+use aoc2023::{assert_example, input, option, Vec2};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
 
 const INPUT: &str = include_str!("../../input/10");
 
 fn main() {
+    check_if_requested(INPUT, assumptions);
+    fuzz_if_requested(
+        &[
+            include_str!("../../input/10-test-1"),
+            include_str!("../../input/10-test-6"),
+        ],
+        parse,
+    );
+    if let Some(style) = option("--render") {
+        let style = match style.as_str() {
+            "plain" => Style::Plain,
+            "ansi" => Style::Ansi,
+            other => panic!("unknown style '{other}', use plain or ansi"),
+        };
+        print!("{}", parse(&input(INPUT)).unwrap().render(style));
+        return;
+    }
+    if let Some(path) = option("--export") {
+        parse(&input(INPUT)).unwrap().picture().save(path).unwrap();
+        return;
+    }
+
     assert_example!(part1, "10-test-1", 4);
     assert_example!(part1, "10-test-2", 8);
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    measure("parse", || parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
 
     assert_example!(part2, "10-test-3", 4);
     assert_example!(part2, "10-test-4", 4);
     assert_example!(part2, "10-test-5", 8);
     assert_example!(part2, "10-test-6", 10);
-    println!("Part 2: {}", part2(INPUT));
+    assert_example!(part1, "10-test-7", 4);
+    assert_example!(part2, "10-test-7", 1);
+    assert!(parse(".S.\n...").is_err());
+    assert!(!assumptions(".S-7\n.|.|\n.L-.").unwrap()[0].holds);
+    assert!(assumptions(".S.\n.x.").is_err());
+    let ragged = parse("-S-7.\n.|.|\n.L-J.").unwrap();
+    assert_eq!(ragged.render(Style::Plain), "OS─┐O\nO│I│O\nO└─┘O\n");
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> usize {
-    parse(input).length() / 2
+    parse(input).unwrap().length() / 2
 }
 
 fn part2(input: &str) -> usize {
-    parse(input).count_empty_tiles_inside()
+    parse(input).unwrap().count_empty_tiles_inside()
+}
+
+/// The start tile is inferred from the loop, these are the shortcuts that were taken before.
+fn assumptions(input: &str) -> anyhow::Result<Vec<Assumption>> {
+    let maze = parse_tiles(input)?;
+    let lines = input.lines().map(|line| line.chars().count()).collect_vec();
+    Ok(vec![
+        Assumption::new(
+            "a closed loop goes through the start",
+            maze.start_pipe().is_some(),
+        ),
+        Assumption::new(
+            "exactly two pipes connect to the start",
+            maze.start_connections().len() == 2,
+        ),
+        Assumption::new("all lines have the same length", lines.iter().all_equal()),
+    ])
 }
 
 struct Maze {
@@ -29,48 +83,74 @@ struct Maze {
 }
 
 impl Maze {
-    /// Find all tile positions that form a closed loop.
+    /// Find all tile positions that form the closed loop through the start.
     fn pipe_loop(&self) -> HashMap<Vec2<i64>, PipeSegmentDirection> {
+        let Tile::Pipe(start_pipe) = self.tiles[&self.start] else {
+            panic!("start tile has not been inferred");
+        };
+        self.walk(start_pipe).expect("start tile closes the loop")
+    }
+
+    /// Follow the pipes from the start, pretending that the start is the given pipe.
+    /// Returns the visited pipes if this leads back into the start through the pipe's other side.
+    fn walk(&self, start_pipe: Pipe) -> Option<HashMap<Vec2<i64>, PipeSegmentDirection>> {
         let mut pipes = HashMap::new();
         let mut position = self.start;
-        let mut came_from = None;
+        let mut came_from = start_pipe.b;
 
         loop {
-            let current_tile = *self.tiles.get(&position).unwrap();
-
-            match current_tile {
-                Tile::Empty => panic!("followed the maze to an empty tile?! {position:?}"),
-                Tile::Start if !pipes.is_empty() => {
-                    break;
-                }
-                Tile::Start => {
-                    let (to, from) = self.start_connections();
-                    let segment_direction = PipeSegmentDirection::new(from, to);
-                    pipes.insert(position, segment_direction);
-                    position += to.to_vec2();
-                    came_from = Some(to.invert());
-                }
-                Tile::Pipe(pipe) => {
-                    let from = came_from.expect("came_from");
-                    let to = pipe.other_side(from).expect("other side");
-                    let segment_direction = PipeSegmentDirection::new(from, to);
-                    pipes.insert(position, segment_direction);
-                    position += to.to_vec2();
-                    came_from = Some(to.invert());
+            let pipe = if position == self.start {
+                start_pipe
+            } else {
+                match self.tiles.get(&position) {
+                    Some(Tile::Pipe(pipe)) if !pipes.contains_key(&position) => *pipe,
+                    _ => return None,
                 }
+            };
+
+            let to = pipe.other_side(came_from)?;
+            pipes.insert(position, PipeSegmentDirection::new(came_from, to));
+            position += to.to_vec2();
+            came_from = to.invert();
+
+            if position == self.start {
+                return (came_from == start_pipe.b).then_some(pipes);
             }
         }
+    }
 
-        pipes
+    /// The pipe at the start that closes a loop, if there is one.
+    fn start_pipe(&self) -> Option<Pipe> {
+        self.start_connections()
+            .into_iter()
+            .tuple_combinations()
+            .map(|(a, b)| Pipe::new(a, b))
+            .find(|&pipe| self.walk(pipe).is_some())
     }
 
-    /// Count the number of tiles inside the polygon described by the pipe loop
-    /// using the [winding number algorithm](https://en.wikipedia.org/wiki/Point_in_polygon#Winding_number_algorithm).
+    /// Replace the start with the pipe that closes a loop.
+    fn infer_start(&mut self) -> anyhow::Result<()> {
+        let Some(start_pipe) = self.start_pipe() else {
+            bail!("no closed loop goes through the start at {:?}", self.start);
+        };
+
+        self.tiles.insert(self.start, Tile::Pipe(start_pipe));
+        Ok(())
+    }
+
+    /// Count the number of tiles inside the polygon described by the pipe loop.
     fn count_empty_tiles_inside(&self) -> usize {
-        let max_dimensions = self.tiles.keys().copied().reduce(Vec2::max).unwrap();
-        let pipe_loop = self.pipe_loop();
+        self.tiles_inside(&self.pipe_loop()).len()
+    }
 
-        let mut tiles_inside = 0;
+    /// Find the tiles inside the polygon described by the pipe loop
+    /// using the [winding number algorithm](https://en.wikipedia.org/wiki/Point_in_polygon#Winding_number_algorithm).
+    fn tiles_inside(
+        &self,
+        pipe_loop: &HashMap<Vec2<i64>, PipeSegmentDirection>,
+    ) -> HashSet<Vec2<i64>> {
+        let max_dimensions = self.max_dimensions();
+        let mut tiles_inside = HashSet::new();
 
         for y in 0..=max_dimensions.y {
             let mut winding = 0;
@@ -96,7 +176,7 @@ impl Maze {
                 } else {
                     last_change = None;
                     if winding != 0 {
-                        tiles_inside += 1;
+                        tiles_inside.insert(position);
                     }
                 }
             }
@@ -109,24 +189,90 @@ impl Maze {
         self.pipe_loop().len()
     }
 
-    /// Returns the pipes that are connected to the start.
-    fn start_connections(&self) -> (Direction, Direction) {
-        let neighbors = Direction::all().into_iter().flat_map(|dir| {
-            let pos = dir.to_vec2() + self.start;
-            self.tiles.get(&pos).map(|&tile| (dir, tile))
-        });
-        let connected_neighbors = neighbors.filter(|(dir, tile)| match tile {
-            Tile::Empty => false,
-            Tile::Start => panic!("move_from_start has start as neighbor?!"),
-            Tile::Pipe(pipe) => pipe.other_side(dir.invert()).is_some(),
+    fn max_dimensions(&self) -> Vec2<i64> {
+        self.tiles.keys().copied().reduce(Vec2::max).unwrap()
+    }
+
+    /// Picture of the loop with the enclosed area highlighted.
+    fn picture(&self) -> Picture {
+        let pipe_loop = self.pipe_loop();
+        let inside = self.tiles_inside(&pipe_loop);
+        let cells = self.tiles.iter().map(|(&position, &tile)| {
+            let color = match tile {
+                _ if position == self.start => Rgb::RED,
+                _ if pipe_loop.contains_key(&position) => Rgb::WHITE,
+                _ if inside.contains(&position) => Rgb::GREEN,
+                Tile::Pipe(_) => Rgb::DARK_GRAY,
+                Tile::Empty | Tile::Start => Rgb::BLACK,
+            };
+            (position, color)
         });
-        let mut connected = connected_neighbors.map(|(dir, _tile)| dir);
+        Picture::new(cells, |&color| color)
+    }
+
+    /// Draw the maze with box drawing characters for the loop.
+    /// Tiles that are not part of the loop are drawn as `I` if they are inside and `O` otherwise.
+    /// With [`Style::Ansi`] the inside is highlighted and pipes that are not part of the loop are dimmed instead.
+    /// Short lines are filled up with empty tiles.
+    fn render(&self, style: Style) -> String {
+        const RESET: &str = "\x1b[0m";
+        const BOLD: &str = "\x1b[1m";
+        const DIM: &str = "\x1b[2m";
+        const START: &str = "\x1b[1;31m";
+        const INSIDE: &str = "\x1b[42m";
+
+        let max_dimensions = self.max_dimensions();
+        let pipe_loop = self.pipe_loop();
+        let inside = self.tiles_inside(&pipe_loop);
+        let mut out = String::new();
 
-        let a = connected.next().expect("first start connection");
-        let b = connected.next().expect("second start connection");
-        assert_eq!(connected.next(), None, "Got a third start connection?!");
+        for y in 0..=max_dimensions.y {
+            for x in 0..=max_dimensions.x {
+                let position = Vec2::new(x, y);
+                let tile = self.tiles.get(&position).copied().unwrap_or(Tile::Empty);
+                let is_inside = inside.contains(&position);
+                let marker = if is_inside { 'I' } else { 'O' };
+
+                match (style, tile) {
+                    (Style::Plain, _) if position == self.start => out.push('S'),
+                    (Style::Ansi, _) if position == self.start => {
+                        out += &format!("{START}S{RESET}")
+                    }
+                    (Style::Plain, Tile::Pipe(pipe)) if pipe_loop.contains_key(&position) => {
+                        out.push(pipe.to_box_char())
+                    }
+                    (Style::Plain, _) => out.push(marker),
+                    (Style::Ansi, Tile::Pipe(pipe)) if pipe_loop.contains_key(&position) => {
+                        out += &format!("{BOLD}{}{RESET}", pipe.to_box_char())
+                    }
+                    (Style::Ansi, tile) => {
+                        let background = if is_inside { INSIDE } else { "" };
+                        let c = match tile {
+                            Tile::Pipe(pipe) => pipe.to_box_char(),
+                            _ => marker,
+                        };
+                        out += &format!("{background}{DIM}{c}{RESET}");
+                    }
+                }
+            }
+            out.push('\n');
+        }
 
-        (a, b)
+        out
+    }
+
+    /// Returns the directions of the neighbors that connect to the start.
+    fn start_connections(&self) -> Vec<Direction> {
+        Direction::all()
+            .into_iter()
+            .filter(|dir| {
+                let pos = dir.to_vec2() + self.start;
+                match self.tiles.get(&pos) {
+                    Some(Tile::Pipe(pipe)) => pipe.other_side(dir.invert()).is_some(),
+                    _ => false,
+                }
+            })
+            .collect()
     }
 }
 
@@ -216,6 +362,33 @@ impl Pipe {
     fn new(a: Direction, b: Direction) -> Self {
         Self { a, b }
     }
+
+    fn connects(&self, a: Direction, b: Direction) -> bool {
+        self.other_side(a) == Some(b)
+    }
+
+    fn to_box_char(self) -> char {
+        if self.connects(Direction::North, Direction::South) {
+            '│'
+        } else if self.connects(Direction::East, Direction::West) {
+            '─'
+        } else if self.connects(Direction::North, Direction::East) {
+            '└'
+        } else if self.connects(Direction::North, Direction::West) {
+            '┘'
+        } else if self.connects(Direction::South, Direction::West) {
+            '┐'
+        } else {
+            '┌'
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Style {
+    Plain,
+    /// Colored with ANSI escape codes for terminals.
+    Ansi,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -225,9 +398,11 @@ enum Tile {
     Pipe(Pipe),
 }
 
-impl From<char> for Tile {
-    fn from(value: char) -> Self {
-        match value {
+impl TryFrom<char> for Tile {
+    type Error = anyhow::Error;
+
+    fn try_from(value: char) -> anyhow::Result<Self> {
+        Ok(match value {
             '|' => Self::Pipe(Pipe::new(Direction::North, Direction::South)),
             '-' => Self::Pipe(Pipe::new(Direction::East, Direction::West)),
             'L' => Self::Pipe(Pipe::new(Direction::North, Direction::East)),
@@ -236,21 +411,31 @@ impl From<char> for Tile {
             'F' => Self::Pipe(Pipe::new(Direction::South, Direction::East)),
             '.' => Self::Empty,
             'S' => Self::Start,
-            other => panic!("unknown tile '{other}'"),
-        }
+            other => bail!("unknown tile '{other}'"),
+        })
     }
 }
 
-fn parse(input: &str) -> Maze {
+fn parse(input: &str) -> anyhow::Result<Maze> {
+    let mut maze = parse_tiles(input)?;
+    maze.infer_start()?;
+    Ok(maze)
+}
+
+/// The maze with the start tile still unknown.
+fn parse_tiles(input: &str) -> anyhow::Result<Maze> {
     let mut tiles = HashMap::new();
     let mut start = None;
 
     for (y, line) in input.lines().enumerate() {
         for (x, c) in line.chars().enumerate() {
-            let tile = c.into();
+            let tile = c.try_into()?;
             let position = Vec2::new(x as i64, y as i64);
 
             if tile == Tile::Start {
+                if start.is_some() {
+                    bail!("there is more than one start");
+                }
                 start = Some(position);
             }
 
@@ -258,7 +443,9 @@ fn parse(input: &str) -> Maze {
         }
     }
 
-    let start = start.expect("start position");
+    let Some(start) = start else {
+        bail!("there is no start");
+    };
 
-    Maze { tiles, start }
+    Ok(Maze { tiles, start })
 }
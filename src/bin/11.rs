@@ -1,89 +1,329 @@
+use anyhow::bail;
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::render::{Picture, Rgb};
 use aoc2023::*;
 use itertools::Itertools;
 
 const INPUT: &str = include_str!("../../input/11");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[include_str!("../../input/11-test")], Universe::parse);
+
+    if let Some(factor) = option("--csv") {
+        let expanded = Universe::parse(&input(INPUT))
+            .unwrap()
+            .expand(factor.parse().unwrap());
+        print!("{}", expanded.distance_csv());
+        return;
+    }
+    if let Some(path) = option("--export") {
+        let factor = option("--factor").map_or(1, |f| f.parse().unwrap());
+        let expanded = Universe::parse(&input(INPUT)).unwrap().expand(factor);
+        expanded.picture().unwrap().save(path).unwrap();
+        return;
+    }
+    if let Some(factor) = option("--report") {
+        let expanded = Universe::parse(&input(INPUT))
+            .unwrap()
+            .expand(factor.parse().unwrap());
+        print!("{}", expanded.report());
+        return;
+    }
+
     assert_example!(part1, "11-test", 374);
-    println!("Part 1: {}", part1(INPUT));
+    let example = Universe::parse(include_str!("../../input/11-test"))
+        .unwrap()
+        .expand(2);
+    assert_eq!(example.distance(0, 6), 15);
+    assert_eq!(example.distance(2, 5), 17);
+    assert_eq!(example.distance(4, 8), 9);
+    assert_eq!(example.distance(7, 8), 5);
+    assert_eq!(example.nearest(4), Some((2, 5)));
+    assert_eq!(example.farthest(4), Some((1, 13)));
+    assert_eq!(
+        example.report().lines().nth(4),
+        Some("Galaxy 5 at (1, 6): nearest 3 at 5, farthest 2 at 13")
+    );
+    let csv = example.distance_csv();
+    assert_eq!(csv.lines().next(), Some("galaxy,1,2,3,4,5,6,7,8,9"));
+    assert_eq!(csv.lines().nth(8), Some("8,15,19,9,14,6,16,10,0,5"));
+    assert_eq!(example.picture().unwrap().size(), Vec2::new(13, 12));
+    let far_out = Universe::parse(include_str!("../../input/11-test"))
+        .unwrap()
+        .expand((1 << 62) + 1);
+    assert!(far_out.picture().is_err());
+    let input = input(INPUT);
+    measure("parse", || Universe::parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_example!(part2, "11-test", 82000210);
-    println!("Part 2: {}", part2(INPUT));
+    assert_example!(sum_for_factors_10_and_100, "11-test", (1030, 8410));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
+}
+
+fn part1(input: &str) -> u128 {
+    Universe::parse(input).unwrap().expand(2).sum_of_distances()
 }
 
-fn part1(input: &str) -> usize {
-    let mut universe = Universe::parse(input);
-    universe.expand(2);
-    universe.sum_of_distances()
+fn part2(input: &str) -> u128 {
+    Universe::parse(input)
+        .unwrap()
+        .expand(1_000_000)
+        .sum_of_distances()
 }
 
-fn part2(input: &str) -> usize {
-    let mut universe = Universe::parse(input);
-    universe.expand(1_000_000);
-    universe.sum_of_distances()
+/// Answer for several factors from a single parse.
+fn sum_for_factors_10_and_100(input: &str) -> (u128, u128) {
+    let universe = Universe::parse(input).unwrap();
+    (
+        universe.expand(10).sum_of_distances(),
+        universe.expand(100).sum_of_distances(),
+    )
 }
 
-type Galaxy = Vec2<usize>;
+type Galaxy = Vec2<u64>;
 
 struct Universe {
     galaxies: Vec<Galaxy>,
+    /// Number of empty columns and rows before each galaxy.
+    empty_before: Vec<Vec2<u64>>,
 }
 
 impl Universe {
-    fn expand(&mut self, factor: usize) {
-        let add = factor - 1;
-        let dimensions = self.galaxies.iter().copied().reduce(Vec2::max).unwrap();
-
-        // Add additional columns
-        for x in (0..=dimensions.x).rev() {
-            let any_galaxies = self.galaxies.iter().any(|g| g.x == x);
-            if any_galaxies {
-                continue;
-            }
-
-            self.galaxies
-                .iter_mut()
-                .filter(|g| g.x > x)
-                .for_each(|g| g.x += add);
-        }
+    /// Replace every empty row and column by `factor` empty rows and columns.
+    fn expand(&self, factor: u64) -> Expanded {
+        let expand = |coordinate: u64, empty_before: u64| {
+            empty_before
+                .checked_mul(factor - 1)
+                .and_then(|add| coordinate.checked_add(add))
+                .expect("expanded coordinate fits into u64")
+        };
+        let galaxies = self
+            .galaxies
+            .iter()
+            .zip(&self.empty_before)
+            .map(|(g, empty)| Vec2::new(expand(g.x, empty.x), expand(g.y, empty.y)))
+            .collect();
+        Expanded { galaxies }
+    }
 
-        // Add additional rows
-        for y in (0..=dimensions.y).rev() {
-            let any_galaxies = self.galaxies.iter().any(|g| g.y == y);
-            if any_galaxies {
-                continue;
-            }
-
-            self.galaxies
-                .iter_mut()
-                .filter(|g| g.y > y)
-                .for_each(|g| g.y += add);
+    /// Fails on anything but galaxies and empty space.
+    fn parse(input: &str) -> anyhow::Result<Self> {
+        if let Some(other) = input
+            .lines()
+            .flat_map(str::chars)
+            .find(|&c| c != '#' && c != '.')
+        {
+            bail!("unknown tile '{other}'");
         }
+
+        let galaxies: Vec<Galaxy> = input.lines().enumerate().flat_map(parse_line).collect();
+        let empty_x = count_empty_before(galaxies.iter().map(|g| g.x));
+        let empty_y = count_empty_before(galaxies.iter().map(|g| g.y));
+        let empty_before = empty_x
+            .into_iter()
+            .zip(empty_y)
+            .map(|(x, y)| Vec2::new(x, y))
+            .collect();
+        Ok(Self {
+            galaxies,
+            empty_before,
+        })
     }
+}
 
-    fn sum_of_distances(&self) -> usize {
-        let pairs = self
+/// The galaxies after expansion, indexed in the order they appear in the input.
+struct Expanded {
+    galaxies: Vec<Galaxy>,
+}
+
+impl Expanded {
+    /// Sum of the distances between all pairs of galaxies.
+    ///
+    /// The taxi cab distance is independent per axis,
+    /// so sum up the distances between all pairs of coordinates on each axis.
+    fn sum_of_distances(&self) -> u128 {
+        sum_of_differences(self.galaxies.iter().map(|g| g.x))
+            + sum_of_differences(self.galaxies.iter().map(|g| g.y))
+    }
+
+    /// White galaxies in black space.
+    fn picture(&self) -> anyhow::Result<Picture> {
+        let cells: Vec<_> = self
             .galaxies
             .iter()
-            .copied()
-            .combinations_with_replacement(2)
-            .map(|list| (list[0], list[1]));
-        pairs.map(distance).sum()
+            .map(|g| match (i64::try_from(g.x), i64::try_from(g.y)) {
+                (Ok(x), Ok(y)) => Ok((Vec2::new(x, y), Rgb::WHITE)),
+                _ => bail!("galaxy at ({}, {}) is too far out to draw", g.x, g.y),
+            })
+            .try_collect()?;
+        Ok(Picture::new(cells, |&color| color))
+    }
+
+    /// Taxi cab distance between galaxy i and j.
+    fn distance(&self, i: usize, j: usize) -> u64 {
+        let (a, b) = (self.galaxies[i], self.galaxies[j]);
+        a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+    }
+
+    /// The other galaxies with their distance to galaxy i.
+    fn others(&self, i: usize) -> impl Iterator<Item = (usize, u64)> + '_ {
+        (0..self.galaxies.len())
+            .filter(move |&j| j != i)
+            .map(move |j| (j, self.distance(i, j)))
     }
 
-    fn parse(input: &str) -> Self {
-        let galaxies = input.lines().enumerate().flat_map(parse_line).collect();
-        Self { galaxies }
+    /// Closest other galaxy and its distance. None if there is only one galaxy.
+    fn nearest(&self, i: usize) -> Option<(usize, u64)> {
+        self.others(i).min_by_key(|&(_, d)| d)
     }
+
+    /// Farthest other galaxy and its distance. None if there is only one galaxy.
+    fn farthest(&self, i: usize) -> Option<(usize, u64)> {
+        self.others(i).max_by_key(|&(_, d)| d)
+    }
+
+    /// Distances between all galaxies, galaxies are numbered from 1 like in the puzzle.
+    fn distance_csv(&self) -> String {
+        let n = self.galaxies.len();
+        let mut csv = format!("galaxy,{}\n", (1..=n).join(","));
+        for i in 0..n {
+            let distances = (0..n).map(|j| self.distance(i, j)).join(",");
+            csv += &format!("{},{distances}\n", i + 1);
+        }
+        csv
+    }
+
+    /// Position, nearest and farthest galaxy for every galaxy, numbered from 1 like in the puzzle.
+    fn report(&self) -> String {
+        let describe = |other: Option<(usize, u64)>| match other {
+            Some((j, d)) => format!("{} at {d}", j + 1),
+            None => "-".to_string(),
+        };
+        (0..self.galaxies.len())
+            .map(|i| {
+                let g = self.galaxies[i];
+                format!(
+                    "Galaxy {} at ({}, {}): nearest {}, farthest {}\n",
+                    i + 1,
+                    g.x,
+                    g.y,
+                    describe(self.nearest(i)),
+                    describe(self.farthest(i)),
+                )
+            })
+            .collect()
+    }
+}
+
+/// For every coordinate, count the unoccupied coordinates before it.
+fn count_empty_before(coordinates: impl Iterator<Item = u64>) -> Vec<u64> {
+    let coordinates = coordinates.collect_vec();
+    let mut result = vec![0; coordinates.len()];
+
+    // Number of distinct occupied coordinates before the current one.
+    let mut occupied_before = 0;
+    let mut previous = None;
+    for i in (0..coordinates.len()).sorted_unstable_by_key(|&i| coordinates[i]) {
+        let c = coordinates[i];
+        if previous.is_some_and(|p| p != c) {
+            occupied_before += 1;
+        }
+        previous = Some(c);
+        result[i] = c - occupied_before;
+    }
+
+    result
+}
+
+/// Sum of `|a - b|` over all pairs of numbers.
+fn sum_of_differences(numbers: impl Iterator<Item = u64>) -> u128 {
+    let mut prefix_sum = 0u128;
+    numbers
+        .sorted_unstable()
+        .enumerate()
+        .map(|(i, n)| {
+            let n = u128::from(n);
+            // n is at least as big as all previous numbers.
+            let difference = n * i as u128 - prefix_sum;
+            prefix_sum += n;
+            difference
+        })
+        .sum()
 }
 
 fn parse_line((line_index, line): (usize, &str)) -> impl Iterator<Item = Galaxy> + '_ {
     line.chars()
         .enumerate()
         .filter(|&(_x, c)| c == '#')
-        .map(move |(x, _c)| Vec2::new(x, line_index))
+        .map(move |(x, _c)| Vec2::new(x as u64, line_index as u64))
 }
 
-/// Taxi cab distance between two galaxies.
-fn distance((a, b): (Galaxy, Galaxy)) -> usize {
-    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2023::differential::{compare, shrink_columns, shrink_lines};
+    use aoc2023::generate;
+    use aoc2023::random::Rng;
+
+    /// Copy every empty row and column until there are `factor` of them and measure every pair of galaxies.
+    fn literal_sum(input: &str, factor: usize) -> u128 {
+        let grid = input
+            .lines()
+            .map(|line| line.chars().collect_vec())
+            .collect_vec();
+        let width = grid.first().map_or(0, Vec::len);
+        let copies = |empty: bool| if empty { factor } else { 1 };
+
+        let columns = (0..width)
+            .flat_map(|x| {
+                let empty = grid.iter().all(|row| row[x] != '#');
+                std::iter::repeat_n(x, copies(empty))
+            })
+            .collect_vec();
+        let expanded = grid
+            .iter()
+            .flat_map(|row| std::iter::repeat_n(row, copies(!row.contains(&'#'))))
+            .collect_vec();
+
+        let galaxies = expanded
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                columns
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &x)| row[x] == '#')
+                    .map(move |(x, _)| (x, y))
+            })
+            .collect_vec();
+        galaxies
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u128)
+            .sum()
+    }
+
+    #[test]
+    fn expansion_matches_literal_expansion() {
+        let generate = |rng: &mut Rng| {
+            let (width, height) = (1 + rng.below(12), 1 + rng.below(12));
+            generate::universe(rng, width, height)
+        };
+        let shrink = |input: &String| [shrink_lines(input), shrink_columns(input)].concat();
+        let reference = |input: &String| (literal_sum(input, 2), literal_sum(input, 10));
+        let fast = |input: &String| {
+            let universe = Universe::parse(input).unwrap();
+            (
+                universe.expand(2).sum_of_distances(),
+                universe.expand(10).sum_of_distances(),
+            )
+        };
+
+        if let Err(disagreement) = compare(2000, generate, shrink, reference, fast) {
+            panic!("{disagreement}");
+        }
+    }
 }
@@ -1,126 +1,163 @@
+use anyhow::{bail, Context};
+use aoc2023::assumptions::{self, check_if_requested};
+use aoc2023::fuzz::fuzz_if_requested;
+use aoc2023::measure::measure;
+use aoc2023::nonogram::{count_arrangements, Arrangements, Cell as Spring};
+use aoc2023::progress::{self, ParallelProgressIterator};
+use aoc2023::random::Rng;
 use aoc2023::*;
-use cached::proc_macro::cached;
 use itertools::Itertools;
-use std::{collections::VecDeque, fmt::Debug, iter};
+use rayon::prelude::*;
+use std::{fmt::Debug, iter};
 
 const INPUT: &str = include_str!("../../input/12");
 
 fn main() {
+    check_if_requested(INPUT, assumptions::none);
+    fuzz_if_requested(&[include_str!("../../input/12-test")], |input| {
+        let rows = parse(input)?;
+        Ok(sum_of_arrangements(&rows))
+    });
+
     assert_example!(part1, "12-test", 21);
-    println!("Part 1: {}", part1(INPUT));
+    assert_example!(check_arrangements, "12-test", 21);
+    assert!(parse("?? 18446744073709551615").is_err());
+    assert!(parse("?? 3").is_err());
+    let input = input(INPUT);
+    measure("parse", || parse(&input).unwrap());
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_example!(part2, "12-test", 525152);
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> usize {
-    input
-        .lines()
-        .map(Row::parse)
-        .map(possible_arrangements)
-        .sum()
+    sum_of_arrangements(&parse(input).unwrap())
 }
 
 fn part2(input: &str) -> usize {
-    input
-        .lines()
-        .map(Row::parse)
+    let rows = parse(input)
+        .unwrap()
+        .into_iter()
         .map(Row::unfold)
-        .map(possible_arrangements)
+        .collect_vec();
+    sum_of_arrangements(&rows)
+}
+
+/// Count the rows in parallel, generated inputs with many rows take a while.
+fn sum_of_arrangements(rows: &[Row]) -> usize {
+    rows.par_iter()
+        .progress_with(progress::bar(rows.len() as u64, "rows"))
+        .map(|row| possible_arrangements(&row.springs, &row.groups))
         .sum()
 }
 
+fn parse(input: &str) -> anyhow::Result<Vec<Row>> {
+    input.lines().map(Row::parse).collect()
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 struct Row {
-    springs: VecDeque<Spring>,
-    groups: VecDeque<usize>,
+    springs: Vec<Spring>,
+    groups: Vec<usize>,
 }
 
 impl Row {
-    fn parse(line: &str) -> Self {
-        let (springs, groups) = line.split_once(' ').unwrap();
-        let springs = springs.chars().map(Spring::from).collect();
-        let groups = groups.split(',').map(|n| n.parse().unwrap()).collect();
-        Self { springs, groups }
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let (springs, groups) = line
+            .split_once(' ')
+            .with_context(|| format!("{line:?} has no groups"))?;
+        let springs: Vec<Spring> = springs
+            .chars()
+            .map(|c| Spring::parse(c).with_context(|| format!("unknown spring '{c}'")))
+            .try_collect()?;
+        let groups: Vec<usize> = groups
+            .split(',')
+            .map(|n| n.parse().with_context(|| format!("{n:?} is not a group")))
+            .try_collect()?;
+        if let Some(group) = groups.iter().find(|&&group| group > springs.len()) {
+            bail!(
+                "a group of {group} does not fit into {} springs",
+                springs.len()
+            );
+        }
+        Ok(Self { springs, groups })
     }
 
     fn unfold(self) -> Self {
-        let springs = iter::repeat(self.springs).take(5);
-        let springs = Itertools::intersperse(springs, [Spring::Unknown].into())
+        let springs = iter::repeat_n(self.springs, 5);
+        let springs = Itertools::intersperse(springs, vec![Spring::Unknown])
             .flatten()
             .collect();
-        let groups = iter::repeat(self.groups).take(5).flatten().collect();
+        let groups = iter::repeat_n(self.groups, 5).flatten().collect();
         Self { springs, groups }
     }
-}
 
-#[cached]
-fn possible_arrangements(mut row: Row) -> usize {
-    // Pop all operational springs on the front and then get the first different one.
-    let first = loop {
-        match row.springs.pop_front() {
-            None => return if row.groups.is_empty() { 1 } else { 0 },
-            Some(Spring::Operational) => continue,
-            Some(Spring::Broken) => break InterestingSpring::Broken,
-            Some(Spring::Unknown) => break InterestingSpring::Unknown,
-        }
-    };
-
-    match first {
-        InterestingSpring::Broken => {
-            // A broken spring requires a run that matches the group.
-            let Some(mut group) = row.groups.pop_front() else {
-                // There was a broken spring but no group
-                return 0;
-            };
-
-            // We already encountered the first broken spring.
-            group -= 1;
-
-            // Pop all required springs or return if impossible.
-            loop {
-                match row.springs.pop_front() {
-                    None if group > 0 => return 0,
-                    None => break,
-                    Some(Spring::Broken | Spring::Unknown) if group > 0 => group -= 1,
-                    Some(Spring::Broken) => return 0,
-                    Some(Spring::Operational) if group > 0 => return 0,
-                    Some(Spring::Operational | Spring::Unknown) => break,
-                }
-            }
-
-            possible_arrangements(row)
-        }
-        InterestingSpring::Unknown => {
-            // We don't know what kind of spring is here, recursively try both options.
-            let mut a = row.clone();
-            let mut b = row;
-            a.springs.push_front(Spring::Broken);
-            b.springs.push_front(Spring::Operational);
-            possible_arrangements(a) + possible_arrangements(b)
-        }
+    fn table(&self) -> Arrangements<'_> {
+        Arrangements::new(&self.springs, &self.groups)
     }
-}
 
-#[derive(Copy, Clone, Debug)]
-enum InterestingSpring {
-    Broken,
-    Unknown,
-}
+    /// Lazily iterate over all concrete arrangements in lexicographic order ('#' before '.').
+    fn arrangements(&self) -> impl Iterator<Item = Vec<Spring>> + '_ {
+        let table = self.table();
+        (0..table.count()).map(move |k| table.nth(k).expect("k is smaller than the count"))
+    }
+
+    /// The k-th arrangement in lexicographic order, without enumerating the previous ones.
+    fn nth_arrangement(&self, k: usize) -> Option<Vec<Spring>> {
+        self.table().nth(k)
+    }
+
+    /// An arrangement chosen uniformly at random.
+    fn random_arrangement(&self, rng: &mut Rng) -> Option<Vec<Spring>> {
+        self.table().sample(rng)
+    }
+
+    /// Whether the springs have no unknowns left and the runs of broken springs match the groups.
+    fn is_valid_arrangement(&self, springs: &[Spring]) -> bool {
+        let matches_pattern = self
+            .springs
+            .iter()
+            .zip_eq(springs)
+            .all(|(&pattern, &spring)| pattern == Spring::Unknown || pattern == spring);
+        let runs = springs
+            .split(|&spring| spring == Spring::Empty)
+            .map(|run| run.len())
+            .filter(|&len| len > 0);
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-enum Spring {
-    Broken,
-    Unknown,
-    Operational,
+        matches_pattern
+            && !springs.contains(&Spring::Unknown)
+            && runs.eq(self.groups.iter().copied())
+    }
 }
 
-impl From<char> for Spring {
-    fn from(value: char) -> Self {
-        match value {
-            '#' => Self::Broken,
-            '.' => Self::Operational,
-            '?' => Self::Unknown,
-            other => panic!("unknown spring '{other}'"),
+/// Enumerate the arrangements of every row and make sure they agree with the count.
+/// Returns the total number of arrangements.
+fn check_arrangements(input: &str) -> usize {
+    let mut rng = Rng::new(12);
+    let mut total = 0;
+
+    for row in parse(input).unwrap() {
+        let arrangements = row.arrangements().collect_vec();
+        assert_eq!(
+            arrangements.len(),
+            possible_arrangements(&row.springs, &row.groups)
+        );
+        assert!(arrangements.iter().all(|a| row.is_valid_arrangement(a)));
+        assert!(arrangements.iter().tuple_windows().all(|(a, b)| a < b));
+        for (k, arrangement) in arrangements.iter().enumerate() {
+            assert_eq!(row.nth_arrangement(k).as_ref(), Some(arrangement));
         }
+        assert_eq!(row.nth_arrangement(arrangements.len()), None);
+        let random = row.random_arrangement(&mut rng).unwrap();
+        assert!(arrangements.contains(&random));
+
+        total += arrangements.len();
     }
+
+    total
+}
+
+/// Count the ways the unknown springs can be assigned so that the runs of broken springs match the groups.
+fn possible_arrangements(springs: &[Spring], groups: &[usize]) -> usize {
+    count_arrangements(springs, groups)
 }
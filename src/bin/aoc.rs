@@ -0,0 +1,453 @@
+//! Tools that work with all days. The days are run as separate programs next to this one.
+
+use anyhow::{bail, Context};
+use aoc2023::allocations::{human_bytes, Allocations};
+use aoc2023::measure::{self, Measurement};
+use aoc2023::random::Rng;
+use aoc2023::{flag, generate, option, progress};
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage:
+    aoc check <day> [--input <file>]
+    aoc fuzz <day> [--iterations <n>] [--seed <n>]
+    aoc gen <day> [--size <n>] [--seed <n>] [--width <n>] [--height <n>] [--unknowns <n>] [--ghosts <n>]
+    aoc run (<day> | --all) [--parallel] [--format text|json|markdown] [--timeout <seconds>] [--memory <MiB>]";
+
+/// Days that `aoc run --all` runs.
+const DAYS: [&str; 12] = [
+    "01", "02", "03", "04", "05", "06", "07", "08", "09", "10", "11", "12",
+];
+
+fn main() -> anyhow::Result<()> {
+    let args = std::env::args().skip(1).collect_vec();
+    match args.iter().map(String::as_str).collect_vec()[..] {
+        ["check", day, ..] => check(day),
+        ["fuzz", day, ..] => fuzz(day),
+        ["gen", day, ..] => gen(day),
+        ["run", "--all", ..] => run(&DAYS),
+        ["run", day, ..] => run(&[day_name(day)?.as_str()]),
+        _ => bail!(USAGE),
+    }
+}
+
+/// Print which assumptions of the day hold for its input, exits with 1 if any is broken
+/// and with 2 if the input cannot be parsed far enough to check them.
+fn check(day: &str) -> anyhow::Result<()> {
+    let day = day_name(day)?;
+    let mut command = Command::new(day_binary(&day)?);
+    command.arg("--check");
+    if let Some(path) = option("--input") {
+        command.args(["--input", &path]);
+    }
+    let status = command.status()?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+/// Fuzz the parser of the day, exits with 1 if an input made it panic or hang.
+fn fuzz(day: &str) -> anyhow::Result<()> {
+    let day = day_name(day)?;
+    let iterations = option("--iterations").unwrap_or_else(|| "10000".to_string());
+    let seed = option("--seed").unwrap_or_else(|| "0".to_string());
+    let status = Command::new(day_binary(&day)?)
+        .args(["--fuzz", &iterations, "--seed", &seed])
+        .status()?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+/// Print a random input for the day. The size is the number of lines,
+/// or the width and height for days with a grid, unless they are given separately.
+fn gen(day: &str) -> anyhow::Result<()> {
+    let number = |name: &str, default: usize| -> anyhow::Result<usize> {
+        option(name).map_or(Ok(default), |value| {
+            value
+                .parse()
+                .with_context(|| format!("{name} {value:?} is not a number"))
+        })
+    };
+    let size = number("--size", 100)?;
+    let (width, height) = (number("--width", size)?, number("--height", size)?);
+    let mut rng = Rng::new(number("--seed", 0)? as u64);
+
+    let input = match day_name(day)?.as_str() {
+        "01" => generate::calibration_lines(&mut rng, size),
+        "02" => generate::games(&mut rng, size),
+        "03" => generate::schematic(&mut rng, width, height),
+        "04" => generate::scratchcards(&mut rng, size, 10, 25),
+        "05" => generate::almanac(&mut rng, 10, size),
+        "06" => generate::races(&mut rng, size, 100),
+        "07" => generate::camel_cards(&mut rng, size),
+        "08" => generate::network(&mut rng, size, number("--ghosts", 6)?),
+        "09" => generate::sequences(&mut rng, size, 21),
+        "10" => generate::pipe_maze(&mut rng, width, height),
+        "11" => generate::universe(&mut rng, width, height),
+        "12" => generate::spring_rows(&mut rng, size, number("--unknowns", 10)?),
+        other => bail!("there is no generator for day {other}"),
+    };
+    print!("{input}");
+    Ok(())
+}
+
+/// How a day ended.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Status {
+    Ok,
+    Failed,
+    Timeout,
+    Oom,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "OK"),
+            Self::Failed => write!(f, "FAILED"),
+            Self::Timeout => write!(f, "TIMEOUT"),
+            Self::Oom => write!(f, "OOM"),
+        }
+    }
+}
+
+/// The answers of a day and how long its phases took.
+/// The parts start from the raw input, so their times include parsing.
+#[derive(Clone, Debug, Serialize)]
+struct DayReport {
+    day: String,
+    part1: Option<String>,
+    part2: Option<String>,
+    #[serde(rename = "parse_ms", serialize_with = "milliseconds")]
+    parse: Option<Duration>,
+    #[serde(rename = "part1_ms", serialize_with = "milliseconds")]
+    part1_time: Option<Duration>,
+    #[serde(rename = "part2_ms", serialize_with = "milliseconds")]
+    part2_time: Option<Duration>,
+    /// Only counted if the days were built with the `count-allocations` feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_allocations: Option<Allocations>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part1_allocations: Option<Allocations>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part2_allocations: Option<Allocations>,
+    status: Status,
+    /// Why the day failed.
+    error: Option<String>,
+}
+
+fn milliseconds<S: Serializer>(duration: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    duration.map(|d| d.as_secs_f64() * 1000.0).serialize(s)
+}
+
+/// A day that exceeds one of these is killed.
+#[derive(Copy, Clone, Debug)]
+struct Limits {
+    timeout: Duration,
+    /// The peak resident memory in bytes, only enforced on Linux.
+    memory: u64,
+}
+
+impl Limits {
+    /// The limits from `--timeout <seconds>` and `--memory <MiB>`, one minute and 4 GiB by default.
+    fn from_options() -> anyhow::Result<Self> {
+        let number = |name: &str, default: f64| -> anyhow::Result<f64> {
+            option(name).map_or(Ok(default), |value| {
+                value
+                    .parse()
+                    .with_context(|| format!("{name} {value:?} is not a number"))
+            })
+        };
+        Ok(Self {
+            timeout: Duration::try_from_secs_f64(number("--timeout", 60.0)?)?,
+            memory: (number("--memory", 4096.0)? * 1024.0 * 1024.0) as u64,
+        })
+    }
+}
+
+/// Run the days and print their answers and timings as a table, exits with 1 if any failed.
+/// Days run one after the other unless `--parallel` is given, which makes their timings less reliable.
+fn run(days: &[&str]) -> anyhow::Result<()> {
+    let format = option("--format").unwrap_or_else(|| "text".to_string());
+    if !["text", "json", "markdown"].contains(&format.as_str()) {
+        bail!("unknown format {format:?}, use text, json or markdown");
+    }
+
+    let limits = Limits::from_options()?;
+
+    let reports: Vec<DayReport> = if flag("--parallel") {
+        days.par_iter().map(|day| run_day(day, limits)).collect()
+    } else {
+        days.iter().map(|day| run_day(day, limits)).collect()
+    };
+
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&reports)?),
+        "markdown" => print!("{}", markdown_table(&reports)),
+        _ => {
+            print!("{}", text_table(&reports));
+            for report in &reports {
+                if let Some(error) = &report.error {
+                    println!("day {}: {error}", report.day);
+                }
+            }
+        }
+    }
+
+    if reports.iter().any(|report| report.status != Status::Ok) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Run a day as its own program, so a panic, a hang or running out of memory fails only this day.
+fn run_day(day: &str, limits: Limits) -> DayReport {
+    let output = day_binary(day).and_then(|path| {
+        let mut command = Command::new(path);
+        command
+            .env(progress::DISABLE, "1")
+            .env(measure::ENABLE, "1");
+        run_limited(&mut command, limits).context("can not run the day")
+    });
+    let mut report = DayReport {
+        day: day.to_string(),
+        part1: None,
+        part2: None,
+        parse: None,
+        part1_time: None,
+        part2_time: None,
+        parse_allocations: None,
+        part1_allocations: None,
+        part2_allocations: None,
+        status: Status::Ok,
+        error: None,
+    };
+
+    let (output, exceeded) = match output {
+        Ok(output) => output,
+        Err(e) => {
+            report.status = Status::Failed;
+            report.error = Some(e.to_string());
+            return report;
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    report.part1 = answer(&stdout, "Part 1: ");
+    report.part2 = answer(&stdout, "Part 2: ");
+    for measurement in String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter_map(Measurement::parse)
+    {
+        let (time, allocations) = match measurement.phase.as_str() {
+            "parse" => (&mut report.parse, &mut report.parse_allocations),
+            "part 1" => (&mut report.part1_time, &mut report.part1_allocations),
+            "part 2" => (&mut report.part2_time, &mut report.part2_allocations),
+            _ => continue,
+        };
+        *time = Some(measurement.duration);
+        *allocations = measurement.allocations;
+    }
+
+    if let Some(status) = exceeded {
+        report.status = status;
+        report.error = Some(match status {
+            Status::Timeout => format!("no result after {:?}", limits.timeout),
+            _ => format!("more than {} MiB resident", limits.memory / 1024 / 1024),
+        });
+    } else if !output.status.success() {
+        report.status = Status::Failed;
+        report.error = Some(failure(&output));
+    }
+    report
+}
+
+/// Run the command and kill it once it exceeds a limit, which is returned as [`Status::Timeout`] or [`Status::Oom`].
+/// The output is what the command wrote until then.
+fn run_limited(command: &mut Command, limits: Limits) -> anyhow::Result<(Output, Option<Status>)> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Pipes are read while the child runs, a full pipe would block it.
+    let read = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            pipe.read_to_end(&mut bytes).map(|_| bytes)
+        })
+    };
+    let stdout = read(Box::new(child.stdout.take().expect("stdout is piped")));
+    let stderr = read(Box::new(child.stderr.take().expect("stderr is piped")));
+
+    let start = Instant::now();
+    let mut exceeded = None;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > limits.timeout {
+            exceeded = Some(Status::Timeout);
+        } else if peak_memory(&child).is_some_and(|peak| peak > limits.memory) {
+            exceeded = Some(Status::Oom);
+        }
+        if exceeded.is_some() {
+            child.kill()?;
+            break child.wait()?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let output = Output {
+        status,
+        stdout: stdout.join().expect("reading stdout does not panic")?,
+        stderr: stderr.join().expect("reading stderr does not panic")?,
+    };
+    Ok((output, exceeded))
+}
+
+/// The peak resident memory of the process in bytes so far. Only Linux tells, through `/proc`.
+fn peak_memory(child: &Child) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", child.id())).ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn answer(stdout: &str, prefix: &str) -> Option<String> {
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix(prefix))
+        .map(str::to_string)
+}
+
+/// The panic message of a day, or its exit status if it did not panic.
+fn failure(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut lines = stderr.lines();
+    lines
+        .find(|line| line.contains("panicked at"))
+        .and_then(|_| lines.next())
+        .map_or_else(|| output.status.to_string(), str::to_string)
+}
+
+/// The reports as rows of cells, starting with the header.
+/// Allocations get their own columns if any day counted them, with the count, the bytes and the peak.
+fn rows(reports: &[DayReport]) -> Vec<Vec<String>> {
+    let counted = reports
+        .iter()
+        .any(|report| report.parse_allocations.is_some());
+    let mut header = vec![
+        "Day",
+        "Part 1",
+        "Part 2",
+        "Parse",
+        "Part 1 time",
+        "Part 2 time",
+    ];
+    if counted {
+        header.extend(["Parse allocs", "Part 1 allocs", "Part 2 allocs"]);
+    }
+    header.push("Status");
+
+    let text = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let time =
+        |value: Option<Duration>| value.map_or_else(|| "-".to_string(), |d| format!("{d:.1?}"));
+    let memory = |value: Option<Allocations>| {
+        value.map_or_else(
+            || "-".to_string(),
+            |a| {
+                format!(
+                    "{} / {} / {}",
+                    a.count,
+                    human_bytes(a.bytes),
+                    human_bytes(a.peak)
+                )
+            },
+        )
+    };
+
+    let rows = reports.iter().map(|report| {
+        let mut row = vec![
+            report.day.clone(),
+            text(&report.part1),
+            text(&report.part2),
+            time(report.parse),
+            time(report.part1_time),
+            time(report.part2_time),
+        ];
+        if counted {
+            row.extend([
+                memory(report.parse_allocations),
+                memory(report.part1_allocations),
+                memory(report.part2_allocations),
+            ]);
+        }
+        row.push(report.status.to_string());
+        row
+    });
+    std::iter::once(header.into_iter().map(str::to_string).collect())
+        .chain(rows)
+        .collect()
+}
+
+fn text_table(reports: &[DayReport]) -> String {
+    let rows = rows(reports);
+    let widths = (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap()
+        })
+        .collect_vec();
+    rows.iter()
+        .map(|row| {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:<width$}"));
+            cells.collect_vec().join("  ").trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+fn markdown_table(reports: &[DayReport]) -> String {
+    let rows = rows(reports);
+    let line = |cells: &[String]| format!("| {} |\n", cells.join(" | "));
+    let separator = vec!["---".to_string(); rows[0].len()];
+    line(&rows[0]) + &line(&separator) + &rows[1..].iter().map(|row| line(row)).collect::<String>()
+}
+
+/// `5` and `05` both mean day 5.
+fn day_name(day: &str) -> anyhow::Result<String> {
+    let number: u32 = day
+        .parse()
+        .with_context(|| format!("{day:?} is not a day"))?;
+    if !(1..=25).contains(&number) {
+        bail!("there is no day {number}");
+    }
+    Ok(format!("{number:02}"))
+}
+
+/// The days are built into the same directory as this program.
+fn day_binary(day: &str) -> anyhow::Result<PathBuf> {
+    let path = std::env::current_exe()?.with_file_name(day);
+    if !path.exists() {
+        bail!(
+            "{} does not exist, build it with `cargo build --bin {day}`",
+            path.display()
+        );
+    }
+    Ok(path)
+}
@@ -0,0 +1,42 @@
+//! Solve a nonogram read from a file, see [`aoc2023::nonogram`] for the format.
+//!
+//! ```shell
+//! cargo run --release --bin nonogram -- input/nonogram-test
+//! cargo run --release --bin nonogram -- --all input/nonogram-test
+//! ```
+
+use anyhow::Context;
+use aoc2023::nonogram::Puzzle;
+use std::{env, fs};
+
+fn main() -> anyhow::Result<()> {
+    let mut all = false;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--all" => all = true,
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.expect("usage: nonogram [--all] <file>");
+
+    let input = fs::read_to_string(&path).with_context(|| format!("reading {path}"))?;
+    let puzzle = Puzzle::parse(&input).with_context(|| format!("parsing {path}"))?;
+
+    let solutions = if all {
+        puzzle.solutions()
+    } else {
+        puzzle.solve().into_iter().collect()
+    };
+
+    if solutions.is_empty() {
+        println!("No solution");
+    }
+    for (i, solution) in solutions.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print!("{solution}");
+    }
+    Ok(())
+}
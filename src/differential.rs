@@ -0,0 +1,123 @@
+//! Differential testing: run a fast solution and a slow but obviously right reference
+//! on many random inputs, and shrink any input where they disagree until it is minimal.
+//!
+//! ```rust
+//! # use aoc2023::differential::{compare, shrink_lines};
+//! let sum = |input: &String| input.lines().map(|l| l.parse::<u32>().unwrap()).sum::<u32>();
+//! let buggy_sum = |input: &String| input.lines().filter(|&l| l != "7").map(|l| l.parse::<u32>().unwrap()).sum::<u32>();
+//!
+//! let random_lines = |rng: &mut aoc2023::random::Rng| {
+//!     (0..rng.below(10)).map(|_| format!("{}\n", rng.below(10))).collect::<String>()
+//! };
+//! assert!(compare(200, random_lines, |i| shrink_lines(i), sum, sum).is_ok());
+//! let disagreement = compare(200, random_lines, |i| shrink_lines(i), sum, buggy_sum).unwrap_err();
+//! assert_eq!(disagreement.input, "7\n");
+//! ```
+
+use crate::random::Rng;
+use std::fmt::{Debug, Display, Formatter};
+
+/// An input for which the two solutions give different results.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Disagreement<I, O> {
+    /// The seed of the generated input before it was shrunk.
+    pub seed: u64,
+    pub input: I,
+    pub reference: O,
+    pub fast: O,
+}
+
+impl<I: Debug, O: Debug> Display for Disagreement<I, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "seed {} shrunk to input {:?}: the reference gives {:?}, the fast solution {:?}",
+            self.seed, self.input, self.reference, self.fast
+        )
+    }
+}
+
+/// Generate inputs from the seeds `0..cases` and compare both solutions on them.
+///
+/// On the first disagreement the input is replaced by the first of its shrunk versions
+/// that still disagrees, until none of them does.
+pub fn compare<I, O: PartialEq>(
+    cases: u64,
+    generate: impl Fn(&mut Rng) -> I,
+    shrink: impl Fn(&I) -> Vec<I>,
+    reference: impl Fn(&I) -> O,
+    fast: impl Fn(&I) -> O,
+) -> Result<(), Disagreement<I, O>> {
+    let disagree = |input: &I| {
+        let (expected, actual) = (reference(input), fast(input));
+        (expected != actual).then_some((expected, actual))
+    };
+
+    for seed in 0..cases {
+        let mut input = generate(&mut Rng::new(seed));
+        let Some(mut results) = disagree(&input) else {
+            continue;
+        };
+
+        while let Some((smaller, smaller_results)) = shrink(&input)
+            .into_iter()
+            .find_map(|candidate| disagree(&candidate).map(|results| (candidate, results)))
+        {
+            (input, results) = (smaller, smaller_results);
+        }
+
+        let (reference, fast) = results;
+        return Err(Disagreement {
+            seed,
+            input,
+            reference,
+            fast,
+        });
+    }
+
+    Ok(())
+}
+
+/// Every version of the input with one line removed.
+///
+/// ```rust
+/// # use aoc2023::differential::shrink_lines;
+/// assert_eq!(shrink_lines("a\nb\n"), ["b\n", "a\n"]);
+/// ```
+pub fn shrink_lines(input: &str) -> Vec<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    (0..lines.len())
+        .map(|skip| {
+            let mut lines = lines.clone();
+            lines.remove(skip);
+            lines.iter().map(|line| format!("{line}\n")).collect()
+        })
+        .collect()
+}
+
+/// Every version of a grid with one column removed.
+///
+/// ```rust
+/// # use aoc2023::differential::shrink_columns;
+/// assert_eq!(shrink_columns("ab\ncd\n"), ["b\nd\n", "a\nc\n"]);
+/// ```
+pub fn shrink_columns(input: &str) -> Vec<String> {
+    let width = input.lines().map(|line| line.chars().count()).max();
+    (0..width.unwrap_or(0))
+        .map(|skip| {
+            input
+                .lines()
+                .map(|line| {
+                    let mut line: String = line
+                        .chars()
+                        .enumerate()
+                        .filter(|&(x, _)| x != skip)
+                        .map(|(_, c)| c)
+                        .collect();
+                    line.push('\n');
+                    line
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,170 @@
+//! A small fuzzer without dependencies: mutate the examples at random and feed them to a parser.
+//!
+//! Parsers must return an error for anything they do not understand, every input that makes them panic
+//! or hang is a bug. Days run their parser with `--fuzz <iterations>`.
+
+use crate::random::Rng;
+use crate::{option, option_or};
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Bytes that are likely to mean something to a parser.
+const INTERESTING: &[u8] = b"0123456789 \n:;,.|#?-=()SLJF7ABZ";
+
+/// Numbers that are likely to overflow or to be special.
+const NUMBERS: [&str; 5] = ["0", "1", "255", "4294967296", "99999999999999999999999"];
+
+/// Apply a few random edits to the input.
+///
+/// ```rust
+/// # use aoc2023::fuzz::mutate;
+/// # use aoc2023::random::Rng;
+/// let mut rng = Rng::new(1);
+/// let mutated = mutate(&mut rng, b"1 2 3");
+/// assert_ne!(mutated, b"1 2 3");
+/// assert!(mutate(&mut rng, b"").len() > 0);
+/// ```
+pub fn mutate(rng: &mut Rng, input: &[u8]) -> Vec<u8> {
+    let mut bytes = input.to_vec();
+    for _ in 0..1 + rng.below(4) {
+        let at = rng.below(bytes.len() + 1);
+        match rng.below(6) {
+            _ if bytes.is_empty() => bytes.push(*rng.choose(INTERESTING)),
+            0 => bytes.insert(at, *rng.choose(INTERESTING)),
+            1 => {
+                let end = (at + 1 + rng.below(8)).min(bytes.len());
+                bytes.drain(at.min(end)..end);
+            }
+            2 => {
+                let i = at.min(bytes.len() - 1);
+                bytes[i] = rng.next_u64() as u8;
+            }
+            3 => {
+                let i = at.min(bytes.len() - 1);
+                bytes[i] = *rng.choose(INTERESTING);
+            }
+            4 => {
+                let start = rng.below(bytes.len());
+                let end = start + 1 + rng.below(bytes.len() - start);
+                let chunk = bytes[start..end].to_vec();
+                bytes.splice(at..at, chunk);
+            }
+            _ => {
+                let number = rng.choose(&NUMBERS).bytes();
+                bytes.splice(at..at, number);
+            }
+        }
+    }
+    bytes
+}
+
+/// How many inputs were accepted and rejected by the parser.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    pub accepted: u64,
+    pub rejected: u64,
+}
+
+/// An input that made the parser panic or hang.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Failure {
+    pub input: String,
+    pub message: String,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} for input {:?}", self.message, self.input)
+    }
+}
+
+/// Parse `iterations` mutations of the examples and stop at the first one that panics
+/// or does not return within the timeout.
+///
+/// ```rust
+/// # use aoc2023::fuzz::fuzz;
+/// # use std::time::Duration;
+/// let parse = |input: &str| -> anyhow::Result<u8> { Ok(input.trim().parse()?) };
+/// let stats = fuzz(&["12"], 100, 0, Duration::from_secs(1), parse).unwrap();
+/// assert_eq!(stats.accepted + stats.rejected, 100);
+///
+/// let bad = |input: &str| -> anyhow::Result<u8> { Ok(input.trim().parse().unwrap()) };
+/// assert!(fuzz(&["12"], 100, 0, Duration::from_secs(1), bad).is_err());
+/// ```
+pub fn fuzz<T: 'static>(
+    examples: &[&str],
+    iterations: u64,
+    seed: u64,
+    timeout: Duration,
+    parse: fn(&str) -> anyhow::Result<T>,
+) -> Result<Stats, Failure> {
+    let mut rng = Rng::new(seed);
+    let mut stats = Stats::default();
+
+    // Panics are expected and reported with their input, the default hook would only add noise.
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let result = (0..iterations).try_for_each(|_| {
+        let example = rng.choose(examples).as_bytes();
+        let input = String::from_utf8_lossy(&mutate(&mut rng, example)).into_owned();
+
+        // Run every input on its own thread, so it can be abandoned if it hangs.
+        let (sender, receiver) = mpsc::channel();
+        let parsing = input.clone();
+        let handle = thread::spawn(move || sender.send(parse(&parsing).is_ok()));
+        let message = match receiver.recv_timeout(timeout) {
+            Ok(true) => {
+                stats.accepted += 1;
+                return Ok(());
+            }
+            Ok(false) => {
+                stats.rejected += 1;
+                return Ok(());
+            }
+            Err(RecvTimeoutError::Timeout) => format!("no result after {timeout:?}"),
+            Err(RecvTimeoutError::Disconnected) => panic_message(handle.join().unwrap_err()),
+        };
+        Err(Failure { input, message })
+    });
+    std::panic::set_hook(hook);
+
+    result.map(|()| stats)
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    format!("panic '{message}'")
+}
+
+/// If the program was started with `--fuzz <iterations>`, fuzz the parser with mutations of the examples
+/// and exit. The exit code is 1 if an input made the parser panic or hang.
+pub fn fuzz_if_requested<T: 'static>(examples: &[&str], parse: fn(&str) -> anyhow::Result<T>) {
+    let Some(iterations) = option("--fuzz") else {
+        return;
+    };
+
+    let iterations = iterations
+        .parse()
+        .expect("--fuzz needs a number of iterations");
+    let seed = option_or("--seed", 0);
+    match fuzz(examples, iterations, seed, Duration::from_secs(1), parse) {
+        Ok(stats) => {
+            println!(
+                "{iterations} inputs: {} accepted, {} rejected",
+                stats.accepted, stats.rejected
+            );
+            std::process::exit(0);
+        }
+        Err(failure) => {
+            println!("{failure}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,470 @@
+//! Random but valid puzzle inputs of any size, for stress and scale tests.
+//!
+//! Everything comes from the [`Rng`], so an input can be reproduced from its seed.
+//!
+//! ```rust
+//! # use aoc2023::generate;
+//! # use aoc2023::random::Rng;
+//! let maze = generate::pipe_maze(&mut Rng::new(1), 7, 5);
+//! assert_eq!(maze.lines().count(), 5);
+//! assert!(maze.lines().all(|line| line.len() == 7));
+//! assert_eq!(maze.matches('S').count(), 1);
+//! ```
+
+use crate::random::Rng;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+/// Calibration lines for day 1 of letters, digits and spelled out digits.
+/// Every line has at least one digit, so it has a value in both parts.
+pub fn calibration_lines(rng: &mut Rng, lines: usize) -> String {
+    const WORDS: [&str; 9] = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+
+    let mut input = String::new();
+    for _ in 0..lines {
+        let mut line = String::new();
+        for _ in 0..rng.below(12) {
+            match rng.below(4) {
+                0 => line.push_str(WORDS[rng.below(WORDS.len())]),
+                1 => line.push(char::from(b'1' + rng.below(9) as u8)),
+                _ => line.push(char::from(b'a' + rng.below(26) as u8)),
+            }
+        }
+        let at = rng.below(line.len() + 1);
+        line.insert(at, char::from(b'1' + rng.below(9) as u8));
+        writeln!(input, "{line}").unwrap();
+    }
+    input
+}
+
+/// Games for day 2, each with up to six draws of up to 20 cubes per colour.
+pub fn games(rng: &mut Rng, games: usize) -> String {
+    let mut input = String::new();
+    for id in 1..=games {
+        let draws: Vec<String> = (0..1 + rng.below(6))
+            .map(|_| {
+                let mut colours = ["red", "green", "blue"];
+                rng.shuffle(&mut colours);
+                colours[..1 + rng.below(3)]
+                    .iter()
+                    .map(|colour| format!("{} {colour}", 1 + rng.below(20)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect();
+        writeln!(input, "Game {id}: {}", draws.join("; ")).unwrap();
+    }
+    input
+}
+
+/// An engine schematic for day 3 with numbers of up to three digits and symbols between them.
+/// Numbers on the same row are always separated by at least one other tile.
+pub fn schematic(rng: &mut Rng, width: usize, height: usize) -> String {
+    const SYMBOLS: &[u8] = b"*#+$/@%=&-";
+
+    let mut input = String::with_capacity((width + 1) * height);
+    for _ in 0..height {
+        let mut row = String::with_capacity(width);
+        while row.len() < width {
+            let number = (1 + rng.below(999)).to_string();
+            let after_number = row.ends_with(|c: char| c.is_ascii_digit());
+            match rng.below(8) {
+                0 if !after_number && row.len() + number.len() <= width => row.push_str(&number),
+                1 => row.push(*rng.choose(SYMBOLS) as char),
+                _ => row.push('.'),
+            }
+        }
+        input.push_str(&row);
+        input.push('\n');
+    }
+    input
+}
+
+/// Scratchcards for day 4 with distinct numbers from 1 to 99 on both sides.
+///
+/// About one card in eight has winning numbers, and never more than there are cards after it.
+/// With less than one winning number per card on average, the copies in part 2 do not grow exponentially.
+/// Panics if there are more numbers than fit on a card.
+///
+/// ```rust
+/// # use aoc2023::generate;
+/// # use aoc2023::random::Rng;
+/// let mut rng = Rng::new(1);
+/// let no_winning = generate::scratchcards(&mut rng, 50, 0, 5);
+/// assert!(no_winning.lines().all(|card| card.contains(":  | ")));
+/// let none_owned = generate::scratchcards(&mut rng, 50, 5, 0);
+/// assert!(none_owned.lines().all(|card| card.ends_with(" | ")));
+/// ```
+pub fn scratchcards(rng: &mut Rng, cards: usize, winning: usize, owned: usize) -> String {
+    assert!(winning + owned <= 99, "there are only 99 different numbers");
+
+    let mut input = String::new();
+    for card in 1..=cards {
+        let mut numbers: Vec<usize> = (1..=99).collect();
+        rng.shuffle(&mut numbers);
+        let most = winning.min(owned).min(cards - card);
+        let matches = if rng.below(8) == 0 && most > 0 {
+            1 + rng.below(most)
+        } else {
+            0
+        };
+
+        // The owned numbers are the matching winning numbers and numbers that are not winning.
+        let (winning, rest) = numbers.split_at(winning);
+        let mut owned = [&winning[..matches], &rest[..owned - matches]].concat();
+        rng.shuffle(&mut owned);
+        let join = |numbers: &[usize]| {
+            numbers
+                .iter()
+                .map(|n| format!("{n:>2}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        writeln!(
+            input,
+            "Card {card:>3}: {} | {}",
+            join(winning),
+            join(&owned)
+        )
+        .unwrap();
+    }
+    input
+}
+
+/// An almanac for day 5 with `seeds` seed ranges and maps of `ranges` ranges each.
+///
+/// Every map cuts the numbers below 2^32 into pieces and moves them to a shuffled order,
+/// so neither the sources nor the destinations overlap.
+pub fn almanac(rng: &mut Rng, seeds: usize, ranges: usize) -> String {
+    const CATEGORIES: [&str; 8] = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+    const END: usize = 1 << 32;
+    assert!(ranges > 0, "a map needs at least one range");
+
+    let seeds: Vec<String> = (0..seeds)
+        .map(|_| {
+            let start = rng.below(END);
+            let len = 1 + rng.below((END - start).min(END / 16));
+            format!("{start} {len}")
+        })
+        .collect();
+    let mut input = format!("seeds: {}\n", seeds.join(" "));
+
+    for (from, to) in CATEGORIES.iter().zip(&CATEGORIES[1..]) {
+        let mut cuts: HashSet<usize> = HashSet::from([0, END]);
+        while cuts.len() < ranges + 1 {
+            cuts.insert(rng.below(END));
+        }
+        let mut cuts: Vec<usize> = cuts.into_iter().collect();
+        cuts.sort_unstable();
+        let mut pieces: Vec<(usize, usize)> =
+            cuts.windows(2).map(|w| (w[0], w[1] - w[0])).collect();
+        rng.shuffle(&mut pieces);
+
+        write!(input, "\n{from}-to-{to} map:\n").unwrap();
+        let mut destination = 0;
+        for (source, len) in pieces {
+            writeln!(input, "{destination} {source} {len}").unwrap();
+            destination += len;
+        }
+    }
+    input
+}
+
+/// Camel Cards hands for day 7. All hands are different, so there is only one ranking.
+/// Panics if there are more hands than possible.
+pub fn camel_cards(rng: &mut Rng, hands: usize) -> String {
+    const CARDS: &[u8] = b"23456789TJQKA";
+    assert!(
+        hands <= 13usize.pow(5),
+        "there are only 13^5 different hands"
+    );
+
+    let mut seen = HashSet::new();
+    let mut input = String::new();
+    while seen.len() < hands {
+        let hand: String = (0..5).map(|_| *rng.choose(CARDS) as char).collect();
+        if seen.insert(hand.clone()) {
+            writeln!(input, "{hand} {}", 1 + rng.below(1000)).unwrap();
+        }
+    }
+    input
+}
+
+/// A network for day 8 with `instructions` instructions and `ghosts` ghosts, the first starting at `AAA`.
+///
+/// Every ghost follows its own path to an end, which it reaches after a prime number of passes
+/// through the instructions. From there it goes around the same loop again. The other direction
+/// of every node leads to a dead end that is never visited.
+/// Panics if there are more ghosts than primes or more nodes than three letter names.
+pub fn network(rng: &mut Rng, instructions: usize, ghosts: usize) -> String {
+    const OTHERS: &[u8] = b"BCDEFGHIJKLMNOPQRSTUVWXY";
+    let mut primes = [3, 5, 7, 11, 13, 17, 19, 23];
+    assert!(instructions > 0, "there must be instructions");
+    assert!(
+        ghosts <= primes.len(),
+        "there are only {} loop lengths",
+        primes.len()
+    );
+    rng.shuffle(&mut primes);
+    let nodes = instructions * primes[..ghosts].iter().sum::<usize>() + instructions;
+    // Names are drawn until an unused one comes up, which gets slow once most of them are taken.
+    assert!(
+        nodes <= 24 * 26 * 26 * 3 / 4,
+        "there are not enough names for {nodes} nodes"
+    );
+
+    let directions: Vec<bool> = (0..instructions).map(|_| rng.below(2) == 0).collect();
+    let mut used = HashSet::from(["AAA".to_string(), "ZZZ".to_string()]);
+    let mut name = |rng: &mut Rng, last: &[u8]| loop {
+        let letter = |rng: &mut Rng| char::from(b'A' + rng.below(26) as u8);
+        let name = format!(
+            "{}{}{}",
+            letter(rng),
+            letter(rng),
+            *rng.choose(last) as char
+        );
+        if used.insert(name.clone()) {
+            return name;
+        }
+    };
+
+    let dead_ends: Vec<String> = (0..instructions).map(|_| name(rng, OTHERS)).collect();
+    let mut lines: Vec<String> = dead_ends
+        .iter()
+        .map(|dead_end| format!("{dead_end} = ({dead_end}, {dead_end})"))
+        .collect();
+    for (ghost, &passes) in primes[..ghosts].iter().enumerate() {
+        let (start, end) = match ghost {
+            0 => ("AAA".to_string(), "ZZZ".to_string()),
+            _ => (name(rng, b"A"), name(rng, b"Z")),
+        };
+        // The path visits the nodes after the start in this order, the end is the last one.
+        let steps = instructions * passes;
+        let mut path: Vec<String> = (1..steps).map(|_| name(rng, OTHERS)).collect();
+        path.push(end);
+
+        for step in 0..steps {
+            let from = if step == 0 { &start } else { &path[step - 1] };
+            let next = &path[step];
+            let dead_end = rng.choose(&dead_ends);
+            let (left, right) = if directions[step % instructions] {
+                (next, dead_end)
+            } else {
+                (dead_end, next)
+            };
+            lines.push(format!("{from} = ({left}, {right})"));
+        }
+        // The end continues like the start, it is the last step of a pass.
+        let (left, right) = if directions[0] {
+            (&path[0], rng.choose(&dead_ends))
+        } else {
+            (rng.choose(&dead_ends), &path[0])
+        };
+        lines.push(format!("{} = ({left}, {right})", path[steps - 1]));
+    }
+    rng.shuffle(&mut lines);
+
+    let instructions: String = directions
+        .iter()
+        .map(|&left| if left { 'L' } else { 'R' })
+        .collect();
+    format!("{instructions}\n\n{}\n", lines.join("\n"))
+}
+
+/// Boat races for day 6 with times from 2 to `max_time`.
+/// Every record is below the best possible distance, so every race can be won.
+pub fn races(rng: &mut Rng, races: usize, max_time: usize) -> String {
+    assert!(max_time >= 2, "races of less than 2 ms can not be won");
+    let times: Vec<usize> = (0..races).map(|_| 2 + rng.below(max_time - 1)).collect();
+    let records: Vec<usize> = times
+        .iter()
+        .map(|&time| rng.below(time * time / 4))
+        .collect();
+    let join = |numbers: &[usize]| {
+        numbers
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    format!(
+        "Time:      {}\nDistance:  {}\n",
+        join(&times),
+        join(&records)
+    )
+}
+
+/// Sequences for day 9, each the values of a random polynomial of degree 0 to 5 at 0, 1, 2 and so on.
+pub fn sequences(rng: &mut Rng, lines: usize, length: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..lines {
+        // Start with the differences at 0 and sum them up like the difference table in the puzzle.
+        let mut differences: Vec<i64> = (0..=rng.below(6))
+            .map(|_| rng.below(41) as i64 - 20)
+            .collect();
+        let mut values = Vec::with_capacity(length);
+        for _ in 0..length {
+            values.push(differences[0].to_string());
+            for i in 0..differences.len() - 1 {
+                differences[i] += differences[i + 1];
+            }
+        }
+        writeln!(input, "{}", values.join(" ")).unwrap();
+    }
+    input
+}
+
+const NORTH: u8 = 1;
+const EAST: u8 = 2;
+const SOUTH: u8 = 4;
+const WEST: u8 = 8;
+
+/// A pipe maze for day 10 with exactly one loop through the start, between random pipes.
+///
+/// The loop is the outline of a shape made of 2×2 blocks, one column of blocks after the other.
+/// Neighbouring columns overlap, so the outline never touches itself.
+/// Panics if the maze is smaller than 3×3.
+pub fn pipe_maze(rng: &mut Rng, width: usize, height: usize) -> String {
+    assert!(
+        width >= 3 && height >= 3,
+        "the maze needs at least 3×3 tiles"
+    );
+
+    let (columns, rows) = ((width - 1) / 2, (height - 1) / 2);
+    let mut filled = vec![vec![false; rows]; columns];
+    let first = rng.below(columns);
+    let last = first + rng.below(columns - first);
+    let mut top = rng.below(rows);
+    let mut bottom = top + rng.below(rows - top);
+    for column in &mut filled[first..=last] {
+        column[top..=bottom].fill(true);
+        let next_top = rng.below(bottom + 1);
+        let lowest = top.max(next_top);
+        (top, bottom) = (next_top, lowest + rng.below(rows - lowest));
+    }
+    let is_filled = |x: usize, y: usize| filled.get(x).and_then(|c| c.get(y)) == Some(&true);
+
+    // The corners of the blocks are the tiles with even coordinates, the sides are between them.
+    let mut connections = vec![vec![0; width]; height];
+    for x in 0..=columns {
+        for y in 0..=rows {
+            let above = y > 0 && is_filled(x, y - 1);
+            if x < columns && above != is_filled(x, y) {
+                connections[2 * y][2 * x] |= EAST;
+                connections[2 * y][2 * x + 1] |= EAST | WEST;
+                connections[2 * y][2 * x + 2] |= WEST;
+            }
+            let left = x > 0 && is_filled(x - 1, y);
+            if y < rows && left != is_filled(x, y) {
+                connections[2 * y][2 * x] |= SOUTH;
+                connections[2 * y + 1][2 * x] |= NORTH | SOUTH;
+                connections[2 * y + 2][2 * x] |= NORTH;
+            }
+        }
+    }
+
+    let mut tiles: Vec<Vec<char>> = connections
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&connection| match connection {
+                    0 => *rng.choose(&['|', '-', 'L', 'J', '7', 'F', '.', '.']),
+                    _ => pipe(connection),
+                })
+                .collect()
+        })
+        .collect();
+
+    // Pipes next to the start that are not part of the loop could connect to it.
+    let on_loop = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| connections[y][x] != 0)
+        .collect::<Vec<_>>();
+    let (x, y) = *rng.choose(&on_loop);
+    tiles[y][x] = 'S';
+    let neighbours = [
+        (x + 1, y),
+        (x, y + 1),
+        (x.wrapping_sub(1), y),
+        (x, y.wrapping_sub(1)),
+    ];
+    for (nx, ny) in neighbours {
+        if ny < height && nx < width && connections[ny][nx] == 0 {
+            tiles[ny][nx] = '.';
+        }
+    }
+
+    tiles
+        .iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+fn pipe(connections: u8) -> char {
+    match connections {
+        c if c == NORTH | SOUTH => '|',
+        c if c == EAST | WEST => '-',
+        c if c == NORTH | EAST => 'L',
+        c if c == NORTH | WEST => 'J',
+        c if c == SOUTH | WEST => '7',
+        c if c == SOUTH | EAST => 'F',
+        other => unreachable!("the loop touches itself, connections {other:#b}"),
+    }
+}
+
+/// An image of the universe for day 11. About every eighth row and column is empty,
+/// the other tiles have a galaxy with a chance of one in twelve.
+pub fn universe(rng: &mut Rng, width: usize, height: usize) -> String {
+    let empty_columns: Vec<bool> = (0..width).map(|_| rng.below(8) == 0).collect();
+    let mut input = String::with_capacity((width + 1) * height);
+    for _ in 0..height {
+        let empty_row = rng.below(8) == 0;
+        for &empty_column in &empty_columns {
+            let galaxy = !empty_row && !empty_column && rng.below(12) == 0;
+            input.push(if galaxy { '#' } else { '.' });
+        }
+        input.push('\n');
+    }
+    input
+}
+
+/// Rows of springs for day 12 with `unknowns` unknown springs each.
+/// The groups are taken from a random row before springs are hidden, so every row has an arrangement.
+pub fn spring_rows(rng: &mut Rng, rows: usize, unknowns: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..rows {
+        let len = unknowns + 1 + rng.below(unknowns + 1);
+        let mut springs: Vec<u8> = (0..len).map(|_| *rng.choose(b"#.")).collect();
+        if !springs.contains(&b'#') {
+            springs[rng.below(len)] = b'#';
+        }
+
+        let groups: Vec<String> = springs
+            .split(|&spring| spring == b'.')
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len().to_string())
+            .collect();
+
+        // A partial Fisher-Yates shuffle picks the positions to hide.
+        let mut positions: Vec<usize> = (0..len).collect();
+        for i in 0..unknowns {
+            positions.swap(i, i + rng.below(len - i));
+            springs[positions[i]] = b'?';
+        }
+
+        let springs = String::from_utf8(springs).unwrap();
+        writeln!(input, "{springs} {}", groups.join(",")).unwrap();
+    }
+    input
+}
@@ -1,5 +1,20 @@
 //! This library contains useful helper functions that may be useful in several problems.
 
+pub mod aho_corasick;
+pub mod allocations;
+pub mod assumptions;
+pub mod differential;
+pub mod fuzz;
+pub mod generate;
+pub mod measure;
+pub mod nonogram;
+pub mod polynomial;
+pub mod progress;
+pub mod random;
+pub mod render;
+
+use anyhow::Context;
+use std::borrow::Cow;
 use std::ops::{Add, AddAssign};
 use std::{
     fmt::{Debug, Display},
@@ -38,20 +53,136 @@ where
     s.split_ascii_whitespace().map(|s| s.parse().unwrap())
 }
 
-/// Concatenate two things.
+/// Parse a whitespace separated list of things, like [`parse_ws_separated`], but fail instead of panicking.
 ///
-/// Panics if the concatenation is not be parseable to keep things simple.
+/// ```rust
+/// # use aoc2023::try_parse_ws_separated;
+/// assert_eq!(try_parse_ws_separated::<u8>("1 2 3").unwrap(), [1, 2, 3]);
+/// assert!(try_parse_ws_separated::<u8>("1 two").is_err());
+/// ```
+pub fn try_parse_ws_separated<T>(s: &str) -> anyhow::Result<Vec<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
+    s.split_ascii_whitespace()
+        .map(|word| {
+            word.parse()
+                .with_context(|| format!("can not parse {word:?}"))
+        })
+        .collect()
+}
+
+/// Concatenate the decimal digits of two numbers.
+///
+/// Panics if the concatenation overflows to keep things simple.
+/// Use [`checked_concat`] or [`Widened`] if that might happen.
 ///
 /// ```rust
 /// # use aoc2023::concat;
-/// assert_eq!(concat(123, 456), 123456);
+/// assert_eq!(concat(123u32, 456), 123456);
+/// # assert_eq!(concat(1u8, 0), 10);
+/// # assert_eq!(concat(0u64, 7), 7);
 /// ```
 pub fn concat<T>(a: T, b: T) -> T
 where
-    T: Display + FromStr,
-    <T as FromStr>::Err: Debug,
+    T: Concat + Display,
 {
-    format!("{a}{b}").parse().unwrap()
+    a.checked_concat(b)
+        .unwrap_or_else(|| panic!("concatenating {a} and {b} overflows"))
+}
+
+/// Concatenate the decimal digits of two numbers.
+///
+/// Returns None if the result does not fit into `T`.
+///
+/// ```rust
+/// # use aoc2023::checked_concat;
+/// assert_eq!(checked_concat(12u8, 3), Some(123));
+/// assert_eq!(checked_concat(25u8, 6), None);
+/// # assert_eq!(checked_concat(u64::MAX / 10, 5), Some(u64::MAX));
+/// # assert_eq!(checked_concat(u64::MAX / 10, 6), None);
+/// # assert_eq!(checked_concat(0u64, u64::MAX), Some(u64::MAX));
+/// # assert_eq!(checked_concat(1u64, u64::MAX), None);
+/// ```
+pub fn checked_concat<T: Concat>(a: T, b: T) -> Option<T> {
+    a.checked_concat(b)
+}
+
+/// Numbers whose decimal digits can be concatenated without going through a string.
+pub trait Concat: Copy {
+    /// Shift `self` left by the number of digits in `other` and add `other`.
+    fn checked_concat(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_concat {
+    ($($t:ty),*) => {$(
+        impl Concat for $t {
+            fn checked_concat(self, other: Self) -> Option<Self> {
+                if self == 0 {
+                    return Some(other);
+                }
+
+                // Smallest power of ten that is greater than other.
+                let mut shift: Self = 10;
+                while shift <= other {
+                    shift = shift.checked_mul(10)?;
+                }
+
+                self.checked_mul(shift)?.checked_add(other)
+            }
+        }
+    )*};
+}
+
+impl_concat!(u8, u16, u32, u64, u128, usize);
+
+/// A number that is stored as [`u64`] as long as it fits and widens to [`u128`] otherwise.
+///
+/// ```rust
+/// # use aoc2023::Widened;
+/// let a = Widened::U64(12345678901);
+/// let b = a.concat(a).unwrap();
+/// assert_eq!(b, Widened::U128(1234567890112345678901));
+/// assert_eq!(Widened::U64(1).concat(Widened::U64(2)), Some(Widened::U64(12)));
+/// assert_eq!(b.concat(b), None);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Widened {
+    U64(u64),
+    U128(u128),
+}
+
+impl Widened {
+    /// Concatenate the decimal digits of both numbers, widening if necessary.
+    ///
+    /// Returns None if the result does not even fit into [`u128`].
+    pub fn concat(self, other: Self) -> Option<Self> {
+        if let (Self::U64(a), Self::U64(b)) = (self, other) {
+            if let Some(n) = a.checked_concat(b) {
+                return Some(Self::U64(n));
+            }
+        }
+
+        u128::from(self)
+            .checked_concat(u128::from(other))
+            .map(Self::U128)
+    }
+}
+
+impl From<u64> for Widened {
+    fn from(value: u64) -> Self {
+        Self::U64(value)
+    }
+}
+
+impl From<Widened> for u128 {
+    fn from(value: Widened) -> Self {
+        match value {
+            Widened::U64(n) => n.into(),
+            Widened::U128(n) => n,
+        }
+    }
 }
 
 /// Return the greatest common divisor.
@@ -93,6 +224,43 @@ where
     a / gcd * b
 }
 
+/// Returns whether the program was started with the given flag, for example `--bench`.
+pub fn flag(name: &str) -> bool {
+    std::env::args().any(|arg| arg == name)
+}
+
+/// Returns the value following the given option, for example `10` for `--factor 10`.
+pub fn option(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+/// Returns the parsed value following the given option, or the default if there is no such option.
+///
+/// Panics if the value can not be parsed.
+pub fn option_or<T>(name: &str, default: T) -> T
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    option(name).map_or(default, |value| {
+        value
+            .parse()
+            .unwrap_or_else(|e| panic!("invalid value {value:?} for {name}: {e:?}"))
+    })
+}
+
+/// The contents of the file following `--input`, or the given input if there is no such option.
+///
+/// Panics if the file can not be read.
+pub fn input(default: &'static str) -> Cow<'static, str> {
+    match option("--input") {
+        Some(path) => std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("can not read {path}: {e}"))
+            .into(),
+        None => default.into(),
+    }
+}
+
 /// Given a function and a name of a file in the `input` directory,
 /// assert that the function applied to the contents of the file returns the expected result.
 /// ```
@@ -0,0 +1,74 @@
+//! How long the phases of a day take and what they allocate, collected by the runner.
+//!
+//! The runner sets [`ENABLE`] for the days it starts. Every phase then writes a [`Measurement`] to stderr,
+//! where it can not be mistaken for an answer.
+//!
+//! ```rust
+//! # use aoc2023::measure::measure;
+//! let answer = measure("part 1", || 6 * 7);
+//! assert_eq!(answer, 42);
+//! ```
+
+use crate::allocations::{self, Allocations};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// Makes [`measure`] report its measurements if it is set to anything.
+pub const ENABLE: &str = "AOC_MEASURE";
+
+/// Marks the lines with measurements in the output of a day.
+const PREFIX: &str = "measurement: ";
+
+/// How long one phase of a day took, like `parse` or `part 1`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub phase: String,
+    pub duration: Duration,
+    /// Only counted with the `count-allocations` feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocations: Option<Allocations>,
+}
+
+impl Measurement {
+    /// Read a measurement from a line written by [`measure`].
+    ///
+    /// ```rust
+    /// # use aoc2023::measure::Measurement;
+    /// # use std::time::Duration;
+    /// let measurement = Measurement {
+    ///     phase: "parse".to_string(),
+    ///     duration: Duration::from_micros(1500),
+    ///     allocations: None,
+    /// };
+    /// assert_eq!(Measurement::parse(&measurement.to_string()), Some(measurement));
+    /// assert_eq!(Measurement::parse("Part 1: 42"), None);
+    /// ```
+    pub fn parse(line: &str) -> Option<Self> {
+        serde_json::from_str(line.strip_prefix(PREFIX)?).ok()
+    }
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{PREFIX}{json}")
+    }
+}
+
+/// Run one phase of a day and report how long it took and what it allocated, if the runner asked for it.
+pub fn measure<T>(phase: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let (result, allocations) = allocations::count(f);
+    let duration = start.elapsed();
+
+    if std::env::var_os(ENABLE).is_some() {
+        let measurement = Measurement {
+            phase: phase.to_string(),
+            duration,
+            allocations: allocations::enabled().then_some(allocations),
+        };
+        eprintln!("{measurement}");
+    }
+    result
+}
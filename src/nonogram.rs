@@ -0,0 +1,555 @@
+//! Count and deduce arrangements of runs in a line and solve whole [nonograms](https://en.wikipedia.org/wiki/Nonogram).
+//!
+//! Puzzles are read from a simple text format. The first block contains the clues for every row,
+//! the second block the clues for every column, one line each with comma separated run lengths.
+//! An empty line is written as `0`. An optional third block contains a partially solved grid.
+//!
+//! ```text
+//! 1,1
+//! 3
+//! 1
+//!
+//! 2
+//! 2
+//! 2
+//!
+//! ???
+//! ?#?
+//! ???
+//! ```
+
+use crate::random::Rng;
+use anyhow::{bail, Context};
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
+use std::iter;
+
+/// Ordered like the characters that represent the cells.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Cell {
+    Filled,
+    Empty,
+    Unknown,
+}
+
+impl Cell {
+    /// The cell for `#`, `.` or `?`.
+    ///
+    /// ```rust
+    /// # use aoc2023::nonogram::Cell;
+    /// assert_eq!(Cell::parse('?'), Some(Cell::Unknown));
+    /// assert_eq!(Cell::parse('x'), None);
+    /// ```
+    pub fn parse(c: char) -> Option<Self> {
+        match c {
+            '#' => Some(Self::Filled),
+            '.' => Some(Self::Empty),
+            '?' => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl From<char> for Cell {
+    fn from(value: char) -> Self {
+        Self::parse(value).unwrap_or_else(|| panic!("unknown cell '{value}'"))
+    }
+}
+
+impl From<Cell> for char {
+    fn from(value: Cell) -> Self {
+        match value {
+            Cell::Filled => '#',
+            Cell::Empty => '.',
+            Cell::Unknown => '?',
+        }
+    }
+}
+
+impl Display for Cell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+/// Count the ways the unknown cells can be assigned so that the runs of filled cells match the clues.
+///
+/// ```rust
+/// # use aoc2023::nonogram::{count_arrangements, Cell};
+/// let cells = "???.###".chars().map(Cell::from).collect::<Vec<_>>();
+/// assert_eq!(count_arrangements(&cells, &[1, 1, 3]), 1);
+/// let cells = "?###????????".chars().map(Cell::from).collect::<Vec<_>>();
+/// assert_eq!(count_arrangements(&cells, &[3, 2, 1]), 10);
+/// assert_eq!(count_arrangements(&cells, &[usize::MAX]), 0);
+/// ```
+pub fn count_arrangements(cells: &[Cell], clues: &[usize]) -> usize {
+    Arrangements::new(cells, clues).count()
+}
+
+/// Table of the number of arrangements of `cells[i..]` with `clues[g..]` for every (i, g).
+///
+/// Counts saturate on overflow. Everything that picks individual arrangements
+/// assumes that the total count did not saturate.
+pub struct Arrangements<'a> {
+    cells: &'a [Cell],
+    clues: &'a [usize],
+    /// Number of empty cells in `cells[..i]`.
+    empty_before: Vec<usize>,
+    counts: Vec<usize>,
+}
+
+impl<'a> Arrangements<'a> {
+    pub fn new(cells: &'a [Cell], clues: &'a [usize]) -> Self {
+        let empty_before = iter::once(0)
+            .chain(cells.iter().scan(0, |count, &cell| {
+                *count += usize::from(cell == Cell::Empty);
+                Some(*count)
+            }))
+            .collect();
+
+        let mut arrangements = Self {
+            cells,
+            clues,
+            empty_before,
+            counts: vec![0; (cells.len() + 1) * (clues.len() + 1)],
+        };
+
+        // Fill the table back to front, every entry only depends on entries further back.
+        for i in (0..=cells.len()).rev() {
+            for g in (0..=clues.len()).rev() {
+                let count = arrangements.compute(i, g);
+                let index = arrangements.index(i, g);
+                arrangements.counts[index] = count;
+            }
+        }
+
+        arrangements
+    }
+
+    /// Number of arrangements of the whole line.
+    pub fn count(&self) -> usize {
+        self.count_from(0, 0)
+    }
+
+    /// Number of arrangements of `cells[i..]` with `clues[g..]`.
+    pub fn count_from(&self, i: usize, g: usize) -> usize {
+        self.counts[self.index(i, g)]
+    }
+
+    /// Returns the cells that are the same in every arrangement, all others are [`Cell::Unknown`].
+    /// Returns None if there is no arrangement at all.
+    ///
+    /// ```rust
+    /// # use aoc2023::nonogram::{Arrangements, Cell};
+    /// let cells = "??????????".chars().map(Cell::from).collect::<Vec<_>>();
+    /// let deduced = Arrangements::new(&cells, &[8]).deduce().unwrap();
+    /// assert_eq!(deduced.iter().join(""), "??######??");
+    /// # use itertools::Itertools;
+    /// # let cells = "#?#".chars().map(Cell::from).collect::<Vec<_>>();
+    /// # assert_eq!(Arrangements::new(&cells, &[1]).deduce(), None);
+    /// # let cells = "???".chars().map(Cell::from).collect::<Vec<_>>();
+    /// # assert_eq!(Arrangements::new(&cells, &[]).deduce().unwrap().iter().join(""), "...");
+    /// ```
+    pub fn deduce(&self) -> Option<Vec<Cell>> {
+        if self.count() == 0 {
+            return None;
+        }
+
+        let len = self.cells.len();
+        // Which states (i, g) can be reached from the start with a valid prefix.
+        let mut reachable = vec![false; self.counts.len()];
+        reachable[self.index(0, 0)] = true;
+        // Difference arrays that mark cells that may be filled or empty in some arrangement.
+        let mut can_fill = vec![0i64; len + 1];
+        let mut can_empty = vec![0i64; len + 1];
+
+        for i in 0..len {
+            for g in 0..=self.clues.len() {
+                if !reachable[self.index(i, g)] {
+                    continue;
+                }
+
+                if self.cells[i] != Cell::Filled && self.count_from(i + 1, g) > 0 {
+                    reachable[self.index(i + 1, g)] = true;
+                    can_empty[i] += 1;
+                    can_empty[i + 1] -= 1;
+                }
+
+                if self.cells[i] == Cell::Empty {
+                    continue;
+                }
+                let Some(next) = self.place_clue(i, g) else {
+                    continue;
+                };
+                if self.count_from(next, g + 1) > 0 {
+                    reachable[self.index(next, g + 1)] = true;
+                    let end = i + self.clues[g];
+                    can_fill[i] += 1;
+                    can_fill[end] -= 1;
+                    can_empty[end] += 1;
+                    can_empty[next] -= 1;
+                }
+            }
+        }
+
+        let prefix_sum = |diffs: Vec<i64>| {
+            diffs
+                .into_iter()
+                .scan(0, |sum, d| {
+                    *sum += d;
+                    Some(*sum > 0)
+                })
+                .collect_vec()
+        };
+        let can_fill = prefix_sum(can_fill);
+        let can_empty = prefix_sum(can_empty);
+
+        let deduced = (0..len)
+            .map(|i| match (can_fill[i], can_empty[i]) {
+                (true, true) => Cell::Unknown,
+                (true, false) => Cell::Filled,
+                (false, true) => Cell::Empty,
+                (false, false) => {
+                    unreachable!("a cell must be something if there is an arrangement")
+                }
+            })
+            .collect();
+
+        Some(deduced)
+    }
+
+    /// Returns the k-th arrangement in lexicographic order, where filled comes before empty.
+    /// Returns None if there are not enough arrangements.
+    ///
+    /// This walks the table once instead of enumerating all previous arrangements.
+    ///
+    /// ```rust
+    /// # use aoc2023::nonogram::{Arrangements, Cell};
+    /// # use itertools::Itertools;
+    /// let cells = "???.###".chars().map(Cell::from).collect_vec();
+    /// let arrangements = Arrangements::new(&cells, &[1, 3]);
+    /// assert_eq!(arrangements.nth(0).unwrap().iter().join(""), "#...###");
+    /// assert_eq!(arrangements.nth(2).unwrap().iter().join(""), "..#.###");
+    /// assert_eq!(arrangements.nth(3), None);
+    /// ```
+    pub fn nth(&self, mut k: usize) -> Option<Vec<Cell>> {
+        if k >= self.count() {
+            return None;
+        }
+
+        let mut result = Vec::with_capacity(self.cells.len());
+        let (mut i, mut g) = (0, 0);
+
+        while i < self.cells.len() {
+            let cell = self.cells[i];
+
+            // Filled comes first, so try to place a run here before leaving the cell empty.
+            if cell != Cell::Empty {
+                if let Some(next) = self.place_clue(i, g) {
+                    let count = self.count_from(next, g + 1);
+                    if k < count {
+                        let end = i + self.clues[g];
+                        result.extend(iter::repeat_n(Cell::Filled, end - i));
+                        result.extend(iter::repeat_n(Cell::Empty, next - end));
+                        (i, g) = (next, g + 1);
+                        continue;
+                    }
+                    k -= count;
+                }
+            }
+
+            debug_assert_ne!(cell, Cell::Filled, "k is always smaller than the count");
+            result.push(Cell::Empty);
+            i += 1;
+        }
+
+        Some(result)
+    }
+
+    /// Lazily iterate over all arrangements in lexicographic order.
+    ///
+    /// ```rust
+    /// # use aoc2023::nonogram::{Arrangements, Cell};
+    /// # use itertools::Itertools;
+    /// let cells = "?#??".chars().map(Cell::from).collect_vec();
+    /// let arrangements = Arrangements::new(&cells, &[2]);
+    /// let all = arrangements.iter().map(|a| a.iter().join("")).collect_vec();
+    /// assert_eq!(all, ["##..", ".##."]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = Vec<Cell>> + '_ {
+        (0..self.count()).map(|k| self.nth(k).expect("k is smaller than the count"))
+    }
+
+    /// Pick an arrangement uniformly at random.
+    /// Returns None if there is no arrangement.
+    pub fn sample(&self, rng: &mut Rng) -> Option<Vec<Cell>> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        self.nth(rng.below(count))
+    }
+
+    fn index(&self, i: usize, g: usize) -> usize {
+        i * (self.clues.len() + 1) + g
+    }
+
+    fn compute(&self, i: usize, g: usize) -> usize {
+        let Some(&cell) = self.cells.get(i) else {
+            return usize::from(g == self.clues.len());
+        };
+
+        let mut count = 0usize;
+        if cell != Cell::Filled {
+            count = count.saturating_add(self.count_from(i + 1, g));
+        }
+        if cell != Cell::Empty {
+            if let Some(next) = self.place_clue(i, g) {
+                count = count.saturating_add(self.count_from(next, g + 1));
+            }
+        }
+        count
+    }
+
+    /// Try to place the run for clue g starting at cell i.
+    /// Returns the index of the first cell after the run and its separator if that is possible.
+    fn place_clue(&self, i: usize, g: usize) -> Option<usize> {
+        let &len = self.clues.get(g)?;
+        let end = i.checked_add(len)?;
+        if end > self.cells.len() {
+            return None;
+        }
+
+        let all_filled = self.empty_before[end] == self.empty_before[i];
+        let separated = self.cells.get(end) != Some(&Cell::Filled);
+        if all_filled && separated {
+            Some((end + 1).min(self.cells.len()))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Grid {
+    /// Row major cells.
+    cells: Vec<Cell>,
+    width: usize,
+}
+
+impl Grid {
+    fn unknown(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![Cell::Unknown; width * height],
+            width,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len() / self.width.max(1)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[y * self.width + x]
+    }
+
+    fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        self.cells[y * self.width + x] = cell;
+    }
+
+    fn row(&self, y: usize) -> Vec<Cell> {
+        (0..self.width).map(|x| self.get(x, y)).collect()
+    }
+
+    fn column(&self, x: usize) -> Vec<Cell> {
+        (0..self.height()).map(|y| self.get(x, y)).collect()
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height() {
+            writeln!(f, "{}", self.row(y).iter().join(""))?;
+        }
+        Ok(())
+    }
+}
+
+/// A nonogram with its clues and the cells that are already known.
+///
+/// ```rust
+/// # use aoc2023::nonogram::Puzzle;
+/// let puzzle = Puzzle::parse("1,1\n3\n1\n\n2\n2\n2").unwrap();
+/// let solutions = puzzle.solutions();
+/// assert_eq!(solutions.len(), 1);
+/// assert_eq!(solutions[0].to_string(), "#.#\n###\n.#.\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    rows: Vec<Vec<usize>>,
+    columns: Vec<Vec<usize>>,
+    grid: Grid,
+}
+
+impl Puzzle {
+    /// Find a single solution.
+    pub fn solve(&self) -> Option<Grid> {
+        let mut solutions = Vec::new();
+        self.search(self.grid.clone(), Some(1), &mut solutions);
+        solutions.pop()
+    }
+
+    /// Find all solutions.
+    ///
+    /// ```rust
+    /// # use aoc2023::nonogram::Puzzle;
+    /// // Both diagonals fit the clues.
+    /// let puzzle = Puzzle::parse("1\n1\n\n1\n1").unwrap();
+    /// assert_eq!(puzzle.solutions().len(), 2);
+    ///
+    /// // The columns fill the first row, which has room for only one run.
+    /// let puzzle = Puzzle::parse("1\n0\n\n1\n1").unwrap();
+    /// assert!(puzzle.solutions().is_empty());
+    /// assert_eq!(puzzle.solve(), None);
+    /// ```
+    pub fn solutions(&self) -> Vec<Grid> {
+        let mut solutions = Vec::new();
+        self.search(self.grid.clone(), None, &mut solutions);
+        solutions
+    }
+
+    /// Deduce cells with line solving and guess a cell whenever that gets stuck.
+    fn search(&self, mut grid: Grid, limit: Option<usize>, solutions: &mut Vec<Grid>) {
+        if limit.is_some_and(|limit| solutions.len() >= limit) {
+            return;
+        }
+
+        if !self.propagate(&mut grid) {
+            return;
+        }
+
+        let Some(unknown) = grid.cells.iter().position(|&c| c == Cell::Unknown) else {
+            solutions.push(grid);
+            return;
+        };
+
+        let (x, y) = (unknown % grid.width, unknown / grid.width);
+        for guess in [Cell::Filled, Cell::Empty] {
+            let mut grid = grid.clone();
+            grid.set(x, y, guess);
+            self.search(grid, limit, solutions);
+        }
+    }
+
+    /// Solve single rows and columns until a whole pass changes nothing anymore.
+    /// That last pass checks every line, so a grid without unknowns matches all clues.
+    /// Returns false if the grid contradicts the clues.
+    fn propagate(&self, grid: &mut Grid) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (y, clues) in self.rows.iter().enumerate() {
+                let Some(deduced) = Arrangements::new(&grid.row(y), clues).deduce() else {
+                    return false;
+                };
+                for (x, cell) in deduced.into_iter().enumerate() {
+                    if cell != grid.get(x, y) {
+                        grid.set(x, y, cell);
+                        changed = true;
+                    }
+                }
+            }
+
+            for (x, clues) in self.columns.iter().enumerate() {
+                let Some(deduced) = Arrangements::new(&grid.column(x), clues).deduce() else {
+                    return false;
+                };
+                for (y, cell) in deduced.into_iter().enumerate() {
+                    if cell != grid.get(x, y) {
+                        grid.set(x, y, cell);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Fails on clues that are not numbers and on a grid that does not match the number of clues.
+    ///
+    /// ```rust
+    /// # use aoc2023::nonogram::Puzzle;
+    /// assert!(Puzzle::parse("1\n1\n\n1\n1\n\n?.\n??").is_ok());
+    /// assert!(Puzzle::parse("1\n1\n\n1\n1\n\n..#\n??").is_err());
+    /// assert!(Puzzle::parse("1\n1\n\n1\n1\n\n??\n??\n??").is_err());
+    /// assert!(Puzzle::parse("1\n1\n\n1\n1\n\n?x\n??").is_err());
+    /// assert!(Puzzle::parse("1\nx\n\n1\n1").is_err());
+    /// ```
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let input = input.trim();
+        let mut blocks = input.split("\n\n");
+        let mut parse_clues = |name: &str| -> anyhow::Result<Vec<Vec<usize>>> {
+            blocks
+                .next()
+                .with_context(|| format!("missing {name} clues"))?
+                .lines()
+                .map(parse_clue)
+                .collect()
+        };
+        let rows = parse_clues("row")?;
+        let columns = parse_clues("column")?;
+
+        let mut grid = Grid::unknown(columns.len(), rows.len());
+        if let Some(known) = blocks.next() {
+            // The grid follows the clues and the two empty lines after them.
+            let first_line = rows.len() + columns.len() + 3;
+            let lines = known.lines().collect_vec();
+            if lines.len() != rows.len() {
+                bail!(
+                    "the grid starting on line {first_line} has {} rows, but there are clues for {}",
+                    lines.len(),
+                    rows.len()
+                );
+            }
+            for (y, line) in lines.into_iter().enumerate() {
+                let width = line.chars().count();
+                if width != columns.len() {
+                    bail!(
+                        "line {} has {width} cells, but there are clues for {} columns",
+                        first_line + y,
+                        columns.len()
+                    );
+                }
+                for (x, c) in line.chars().enumerate() {
+                    let cell = Cell::parse(c).with_context(|| {
+                        format!("unknown cell '{c}' on line {}", first_line + y)
+                    })?;
+                    grid.set(x, y, cell);
+                }
+            }
+        }
+
+        Ok(Self {
+            rows,
+            columns,
+            grid,
+        })
+    }
+}
+
+fn parse_clue(line: &str) -> anyhow::Result<Vec<usize>> {
+    line.split(',')
+        .map(|n| {
+            n.trim()
+                .parse()
+                .with_context(|| format!("{n:?} is not a clue"))
+        })
+        .filter_ok(|&n| n != 0)
+        .collect()
+}
@@ -0,0 +1,75 @@
+//! Fit polynomials to integer sequences and extrapolate them.
+
+use itertools::Itertools;
+
+/// The polynomial of minimal degree that passes through a sequence of values,
+/// where the n-th value of the sequence is the value of the polynomial at n.
+///
+/// The polynomial is stored in [Newton's forward difference form](https://en.wikipedia.org/wiki/Newton_polynomial),
+/// so that all arithmetic stays in integers and is exact.
+///
+/// ```rust
+/// # use aoc2023::polynomial::Polynomial;
+/// // n^2 + 1
+/// let p = Polynomial::fit([1, 2, 5, 10]);
+/// assert_eq!(p.degree(), 2);
+/// assert_eq!(p.at(4), 17);
+/// assert_eq!(p.at(-1), 2);
+/// assert_eq!(p.at(26501365), 702322346863226);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Polynomial {
+    /// The leading value of each row of the difference table.
+    differences: Vec<i128>,
+}
+
+impl Polynomial {
+    /// Fit a polynomial through all values.
+    ///
+    /// ```rust
+    /// # use aoc2023::polynomial::Polynomial;
+    /// let p = Polynomial::fit([0, 3, 6, 9, 12, 15]);
+    /// assert_eq!(p.degree(), 1);
+    /// assert_eq!(p.at(6), 18);
+    /// # assert_eq!(Polynomial::fit([]).at(3), 0);
+    /// # assert_eq!(Polynomial::fit([7]).at(3), 7);
+    /// # assert_eq!(Polynomial::fit([0, 0, 0, 1]).degree(), 3);
+    /// ```
+    pub fn fit(values: impl IntoIterator<Item = i64>) -> Self {
+        let mut row = values.into_iter().map(i128::from).collect_vec();
+        let mut differences = Vec::new();
+
+        while row.iter().any(|&n| n != 0) {
+            differences.push(row[0]);
+            row = row.iter().tuple_windows().map(|(a, b)| b - a).collect();
+        }
+
+        Self { differences }
+    }
+
+    /// The degree of the polynomial. The zero polynomial has degree 0.
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// Evaluate the polynomial at x. Negative values extrapolate backwards.
+    ///
+    /// Panics on overflow to keep things simple.
+    pub fn at(&self, x: i64) -> i128 {
+        let x = i128::from(x);
+        let mut result = 0i128;
+        // Binomial coefficient (x choose k), which is always an integer.
+        let mut binomial = 1i128;
+
+        for (k, &difference) in self.differences.iter().enumerate() {
+            if k > 0 {
+                let k = k as i128;
+                binomial = binomial.checked_mul(x - k + 1).expect("overflow") / k;
+            }
+            let term = difference.checked_mul(binomial).expect("overflow");
+            result = result.checked_add(term).expect("overflow");
+        }
+
+        result
+    }
+}
@@ -0,0 +1,41 @@
+//! Progress bars for parts that run for a while. They are drawn on stderr, so they never mix with the answers.
+//!
+//! Bars are hidden when stderr is not a terminal, when benchmarking with `--bench`,
+//! or when [`DISABLE`] is set, which the runner does for the days it starts.
+//!
+//! ```rust
+//! # use aoc2023::progress::{self, ParallelProgressIterator};
+//! # use rayon::prelude::*;
+//! let numbers: Vec<u64> = (1..=100).collect();
+//! let sum: u64 = numbers.par_iter().progress_with(progress::bar(100, "numbers")).sum();
+//! assert_eq!(sum, 5050);
+//! ```
+
+use crate::flag;
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use std::io::IsTerminal;
+
+pub use indicatif::{ParallelProgressIterator, ProgressIterator};
+
+/// Hides all progress bars if it is set to anything.
+pub const DISABLE: &str = "AOC_NO_PROGRESS";
+
+/// Whether progress bars are shown.
+pub fn enabled() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os(DISABLE).is_none() && !flag("--bench")
+}
+
+/// A bar over `total` steps, to use with `progress_with` on iterators and parallel iterators.
+/// It is hidden if progress is not [`enabled`] and removed when it is done.
+pub fn bar(total: u64, message: &'static str) -> ProgressBar {
+    if !enabled() {
+        return ProgressBar::hidden();
+    }
+
+    let style = ProgressStyle::with_template("{msg} {wide_bar} {pos}/{len} {elapsed}, {eta} left")
+        .expect("the template is valid");
+    ProgressBar::new(total)
+        .with_style(style)
+        .with_message(message)
+        .with_finish(ProgressFinish::AndClear)
+}
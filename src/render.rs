@@ -0,0 +1,191 @@
+//! Export grids of cells as images without any image dependencies.
+//!
+//! Every cell becomes a square. Images can be written as [SVG](https://en.wikipedia.org/wiki/SVG)
+//! or as binary [PPM](https://netpbm.sourceforge.net/doc/ppm.html).
+
+use crate::Vec2;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Self = Self(0, 0, 0);
+    pub const WHITE: Self = Self(255, 255, 255);
+    pub const GRAY: Self = Self(128, 128, 128);
+    pub const DARK_GRAY: Self = Self(48, 48, 48);
+    pub const RED: Self = Self(220, 50, 47);
+    pub const GREEN: Self = Self(133, 153, 0);
+    pub const BLUE: Self = Self(38, 139, 210);
+    pub const YELLOW: Self = Self(181, 137, 0);
+
+    fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// Biggest PPM that will be written, so a typo in a scale does not fill the disk.
+const MAX_PPM_PIXELS: u64 = 100_000_000;
+
+/// Colored cells, everything between them is filled with the background.
+///
+/// ```rust
+/// # use aoc2023::render::{Picture, Rgb};
+/// # use aoc2023::Vec2;
+/// let cells = [(Vec2::new(0, 0), '#'), (Vec2::new(1, 1), '.')];
+/// let picture = Picture::new(cells, |&c| if c == '#' { Rgb::WHITE } else { Rgb::GRAY });
+/// assert_eq!(picture.size(), Vec2::new(2, 2));
+///
+/// let ppm = picture.to_ppm(1).unwrap();
+/// assert!(ppm.starts_with(b"P6\n2 2\n255\n"));
+/// assert_eq!(&ppm[11..], [255, 255, 255, 0, 0, 0, 0, 0, 0, 128, 128, 128]);
+/// assert!(picture.to_ppm(u64::MAX).is_err());
+///
+/// let svg = picture.to_svg();
+/// assert!(svg.contains(r##"<rect x="1" y="1" width="1" height="1" fill="#808080"/>"##));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Picture {
+    cells: HashMap<Vec2<i64>, Rgb>,
+    /// Top left corner.
+    min: Vec2<i64>,
+    /// Bottom right corner.
+    max: Vec2<i64>,
+    background: Rgb,
+}
+
+impl Picture {
+    /// Color every cell with the palette.
+    pub fn new<T>(
+        cells: impl IntoIterator<Item = (Vec2<i64>, T)>,
+        palette: impl Fn(&T) -> Rgb,
+    ) -> Self {
+        let cells: HashMap<_, _> = cells
+            .into_iter()
+            .map(|(position, cell)| (position, palette(&cell)))
+            .collect();
+
+        let mut min = Vec2::new(i64::MAX, i64::MAX);
+        let mut max = Vec2::new(i64::MIN, i64::MIN);
+        for &position in cells.keys() {
+            min = Vec2::new(min.x.min(position.x), min.y.min(position.y));
+            max = max.max(position);
+        }
+        if cells.is_empty() {
+            (min, max) = (Vec2::new(0, 0), Vec2::new(0, 0));
+        }
+
+        Self {
+            cells,
+            min,
+            max,
+            background: Rgb::BLACK,
+        }
+    }
+
+    pub fn with_background(self, background: Rgb) -> Self {
+        Self { background, ..self }
+    }
+
+    /// Width and height in cells.
+    pub fn size(&self) -> Vec2<u64> {
+        if self.cells.is_empty() {
+            return Vec2::new(0, 0);
+        }
+        Vec2::new(
+            self.max.x.abs_diff(self.min.x) + 1,
+            self.max.y.abs_diff(self.min.y) + 1,
+        )
+    }
+
+    /// One rectangle per cell, so this also works for huge sparse pictures.
+    pub fn to_svg(&self) -> String {
+        let size = self.size();
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" shape-rendering="crispEdges">"#,
+            w = size.x,
+            h = size.y,
+        );
+        svg.push('\n');
+        writeln!(
+            svg,
+            r#"<rect width="{}" height="{}" fill="{}"/>"#,
+            size.x,
+            size.y,
+            self.background.hex()
+        )
+        .unwrap();
+
+        // Sort the cells, so the output is the same every time.
+        let mut cells: Vec<_> = self.cells.iter().collect();
+        cells.sort_unstable_by_key(|(position, _)| (position.y, position.x));
+        for (position, color) in cells {
+            writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="1" height="1" fill="{}"/>"#,
+                position.x - self.min.x,
+                position.y - self.min.y,
+                color.hex()
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Binary PPM where every cell is a square of `scale` pixels.
+    /// Returns an error if the image would be unreasonably big.
+    pub fn to_ppm(&self, scale: u64) -> io::Result<Vec<u8>> {
+        let size = self.size();
+        let too_big = || {
+            io::Error::other(format!(
+                "{}x{} cells at scale {scale} is too big for a PPM, use SVG instead",
+                size.x, size.y
+            ))
+        };
+        let width = size.x.checked_mul(scale).ok_or_else(too_big)?;
+        let height = size.y.checked_mul(scale).ok_or_else(too_big)?;
+        if width.saturating_mul(height) > MAX_PPM_PIXELS {
+            return Err(too_big());
+        }
+
+        let mut ppm = format!("P6\n{width} {height}\n255\n").into_bytes();
+        ppm.reserve((width * height * 3) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let position = Vec2::new(
+                    self.min.x + (x / scale) as i64,
+                    self.min.y + (y / scale) as i64,
+                );
+                let Rgb(r, g, b) = self
+                    .cells
+                    .get(&position)
+                    .copied()
+                    .unwrap_or(self.background);
+                ppm.extend([r, g, b]);
+            }
+        }
+
+        Ok(ppm)
+    }
+
+    /// Write the picture, the format is chosen by the extension (`.svg` or `.ppm`).
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let data = match path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => self.to_svg().into_bytes(),
+            Some("ppm") => self.to_ppm(8)?,
+            _ => {
+                return Err(io::Error::other(format!(
+                    "unknown image format for {}, use .svg or .ppm",
+                    path.display()
+                )))
+            }
+        };
+        std::fs::write(path, data)
+    }
+}
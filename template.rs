@@ -1,12 +1,14 @@
+use aoc2023::measure::measure;
 use aoc2023::*;
 
 const INPUT: &str = include_str!("../../input/XX");
 
 fn main() {
     assert_example!(part1, "XX-test", 0);
-    println!("Part 1: {}", part1(INPUT));
+    let input = input(INPUT);
+    println!("Part 1: {}", measure("part 1", || part1(&input)));
     assert_example!(part2, "XX-test", 0);
-    println!("Part 2: {}", part2(INPUT));
+    println!("Part 2: {}", measure("part 2", || part2(&input)));
 }
 
 fn part1(input: &str) -> usize {